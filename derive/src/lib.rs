@@ -0,0 +1,349 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Derive macros for `ethabi-decode`, re-exported by it under the `derive` feature.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+	ext::IdentExt, parse_macro_input, spanned::Spanned, Data, DeriveInput, Expr, ExprLit, GenericArgument, Index, Lit,
+	LitStr, Member, PathArguments, Type,
+};
+use tiny_keccak::{Hasher, Keccak};
+
+mod signature;
+
+/// Field of the struct a macro is derived for.
+struct Field {
+	member: Member,
+	ty: Type,
+	indexed: bool,
+	/// Solidity type given with `#[abi_type = "..."]`.
+	abi_type: Option<LitStr>,
+}
+
+/// Returns the fields of a struct in declaration order, failing for enums and unions.
+fn fields(input: &DeriveInput, derive: &str) -> syn::Result<Vec<Field>> {
+	let data = match &input.data {
+		Data::Struct(data) => data,
+		_ => {
+			let message = format!("{} can only be derived for structs", derive);
+			return Err(syn::Error::new_spanned(&input.ident, message));
+		}
+	};
+
+	data.fields
+		.iter()
+		.enumerate()
+		.map(|(i, field)| {
+			let member = match &field.ident {
+				Some(ident) => Member::Named(ident.clone()),
+				None => Member::Unnamed(Index::from(i)),
+			};
+			let (mut indexed, mut abi_type) = (false, None);
+			for attr in &field.attrs {
+				if attr.path().is_ident("indexed") {
+					attr.meta.require_path_only()?;
+					indexed = true;
+				} else if attr.path().is_ident("abi_type") {
+					let value = &attr.meta.require_name_value()?.value;
+					match value {
+						Expr::Lit(ExprLit { lit: Lit::Str(lit), .. }) => abi_type = Some(lit.clone()),
+						_ => return Err(syn::Error::new_spanned(value, "expected a type like \"(address,uint256)\"")),
+					}
+				}
+			}
+			Ok(Field { member, ty: field.ty.clone(), indexed, abi_type })
+		})
+		.collect()
+}
+
+/// Returns the Solidity type of a field type `ethabi-decode` implements
+/// `Tokenizable` for, like `bytes32[]` for `Vec<H256>`, or `None` for any other type.
+fn type_name(ty: &Type) -> Option<String> {
+	match ty {
+		Type::Group(group) => type_name(&group.elem),
+		Type::Paren(paren) => type_name(&paren.elem),
+		Type::Array(array) => {
+			let len = match &array.len {
+				Expr::Lit(ExprLit { lit: Lit::Int(len), .. }) => len.base10_parse::<usize>().ok()?,
+				_ => return None,
+			};
+			match is_u8(&array.elem) {
+				true => Some(format!("bytes{}", len)),
+				false => Some(format!("{}[{}]", type_name(&array.elem)?, len)),
+			}
+		}
+		Type::Tuple(tuple) if !tuple.elems.is_empty() => {
+			let components = tuple.elems.iter().map(type_name).collect::<Option<Vec<_>>>()?;
+			Some(format!("({})", components.join(",")))
+		}
+		Type::Path(path) if path.qself.is_none() => {
+			let segment = path.path.segments.last()?;
+			match (segment.ident.to_string().as_str(), &segment.arguments) {
+				("Address", PathArguments::None) => Some("address".into()),
+				("U256", PathArguments::None) => Some("uint256".into()),
+				("H256", PathArguments::None) => Some("bytes32".into()),
+				("bool", PathArguments::None) => Some("bool".into()),
+				("Vec", PathArguments::AngleBracketed(args)) if args.args.len() == 1 => match &args.args[0] {
+					GenericArgument::Type(element) if is_u8(element) => Some("bytes".into()),
+					GenericArgument::Type(element) => Some(format!("{}[]", type_name(element)?)),
+					_ => None,
+				},
+				_ => None,
+			}
+		}
+		_ => None,
+	}
+}
+
+fn is_u8(ty: &Type) -> bool {
+	matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("u8"))
+}
+
+/// Implements `Tokenizable` for a struct, as the tuple of its fields.
+///
+/// Every field type must implement `Tokenizable`. A struct like
+/// `struct Deposit { sender: Address, amount: U256, payload: Vec<u8> }`
+/// has the type `(address,uint256,bytes)`.
+#[proc_macro_derive(EthAbiType)]
+pub fn derive_eth_abi_type(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	expand_eth_abi_type(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand_eth_abi_type(input: &DeriveInput) -> syn::Result<TokenStream2> {
+	let fields = fields(input, "EthAbiType")?;
+	let name = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	let len = fields.len();
+	let tys = fields.iter().map(|f| &f.ty);
+	let members = fields.iter().map(|f| &f.member).collect::<Vec<_>>();
+	let indices = 0..len;
+
+	Ok(quote! {
+		impl #impl_generics ::ethabi_decode::Tokenizable for #name #ty_generics #where_clause {
+			const KIND: ::ethabi_decode::StaticParamKind = ::ethabi_decode::StaticParamKind::Tuple(&[
+				#( <#tys as ::ethabi_decode::Tokenizable>::KIND ),*
+			]);
+
+			#[allow(unused_mut)]
+			fn from_token(token: ::ethabi_decode::Token) -> ::core::result::Result<Self, ::ethabi_decode::Error> {
+				let mut tokens = ::ethabi_decode::__private::components(token, #len)?;
+				::core::result::Result::Ok(Self {
+					#( #members: ::ethabi_decode::__private::next(
+						&mut tokens,
+						::ethabi_decode::PathSegment::Component(#indices),
+					)?, )*
+				})
+			}
+
+			fn into_token(self) -> ::ethabi_decode::Token {
+				::ethabi_decode::Token::Tuple(::ethabi_decode::__private::Vec::from([
+					#( ::ethabi_decode::Tokenizable::into_token(self.#members) ),*
+				]))
+			}
+		}
+	})
+}
+
+/// Implements `EthEvent` for a struct, with one event param per field.
+///
+/// Fields marked `#[indexed]` become topics. The event is named after the
+/// struct unless overridden with `#[event(name = "Transfer")]`, and can be
+/// made anonymous with `#[event(anonymous)]`. Every field type must implement
+/// `Tokenizable`.
+///
+/// The signature and its topic are rendered at compile time from the Solidity
+/// types of the fields. These are told from the field types `Tokenizable` is
+/// implemented for by `ethabi-decode`, like `Vec<H256>` or `(Address, bool)`;
+/// other fields, like structs deriving `EthAbiType`, must give theirs with
+/// `#[abi_type = "(address,uint256,bytes)"]`. Using the event fails to compile
+/// if a field type does not match its Solidity type.
+///
+/// Indexed fields of dynamic types (`bytes`, `string`, arrays and tuples) are
+/// only logged as their hash, so logs with such fields can be encoded but not
+/// decoded back into the struct.
+#[proc_macro_derive(EthEvent, attributes(indexed, abi_type, event))]
+pub fn derive_eth_event(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	expand_eth_event(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand_eth_event(input: &DeriveInput) -> syn::Result<TokenStream2> {
+	let fields = fields(input, "EthEvent")?;
+	let name = &input.ident;
+
+	let mut event_name = LitStr::new(&name.to_string(), name.span());
+	let mut anonymous = false;
+	for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("event")) {
+		attr.parse_nested_meta(|meta| {
+			if meta.path.is_ident("name") {
+				event_name = meta.value()?.parse()?;
+				Ok(())
+			} else if meta.path.is_ident("anonymous") {
+				anonymous = true;
+				Ok(())
+			} else {
+				Err(meta.error("expected `name` or `anonymous`"))
+			}
+		})?;
+	}
+
+	let types = fields
+		.iter()
+		.map(|f| match (&f.abi_type, type_name(&f.ty)) {
+			(Some(abi_type), _) => Ok((abi_type.value(), abi_type.span())),
+			(None, Some(name)) => Ok((name, f.ty.span())),
+			(None, None) => {
+				let message = "unknown Solidity type, give it with `#[abi_type = \"...\"]`";
+				Err(syn::Error::new_spanned(&f.ty, message))
+			}
+		})
+		.collect::<syn::Result<Vec<_>>>()?;
+	let kinds = types
+		.iter()
+		.map(|(name, span)| signature::parse_kind(name).map_err(|e| syn::Error::new(*span, e)))
+		.collect::<syn::Result<Vec<_>>>()?;
+	let signature = signature::signature(&event_name.value(), &kinds);
+	let topic = keccak256(signature.as_bytes());
+
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	let len = fields.len();
+	let checks = fields.iter().zip(&kinds).map(|(f, kind)| {
+		let ty = &f.ty;
+		let member = match &f.member {
+			Member::Named(ident) => ident.unraw().to_string(),
+			Member::Unnamed(index) => index.index.to_string(),
+		};
+		let message = format!("the type of field `{}` is not `{}`", member, kind);
+		let kind = signature::static_kind(kind);
+		quote!(::core::assert!(
+			::ethabi_decode::__private::same_kind(&<#ty as ::ethabi_decode::Tokenizable>::KIND, &#kind),
+			#message,
+		);)
+	});
+	let params = fields.iter().zip(&kinds).map(|(f, kind)| {
+		let (kind, indexed) = (signature::static_kind(kind), f.indexed);
+		let name = match &f.member {
+			Member::Named(ident) => {
+				let name = ident.unraw().to_string();
+				quote!(::core::option::Option::Some(#name))
+			}
+			Member::Unnamed(_) => quote!(::core::option::Option::None),
+		};
+		quote!(::ethabi_decode::StaticParam {
+			kind: #kind,
+			indexed: #indexed,
+			names: ::ethabi_decode::StaticParamNames { name: #name, components: &[] },
+		})
+	});
+	let members = fields.iter().map(|f| &f.member).collect::<Vec<_>>();
+	let indices = 0..len;
+
+	Ok(quote! {
+		impl #impl_generics ::ethabi_decode::EthEvent for #name #ty_generics #where_clause {
+			const SIGNATURE: &'static str = #signature;
+			const TOPIC: ::ethabi_decode::H256 = ::ethabi_decode::H256([#( #topic ),*]);
+			const INPUTS: &'static [::ethabi_decode::StaticParam] = {
+				#( #checks )*
+				&[#( #params ),*]
+			};
+			const ANONYMOUS: bool = #anonymous;
+
+			#[allow(unused_mut)]
+			fn from_tokens(
+				tokens: ::ethabi_decode::__private::Vec<::ethabi_decode::Token>,
+			) -> ::core::result::Result<Self, ::ethabi_decode::Error> {
+				let mut tokens = ::ethabi_decode::__private::tokens(tokens, #len)?;
+				::core::result::Result::Ok(Self {
+					#( #members: ::ethabi_decode::__private::next(
+						&mut tokens,
+						::ethabi_decode::PathSegment::Input(#indices),
+					)?, )*
+				})
+			}
+
+			fn into_tokens(self) -> ::ethabi_decode::__private::Vec<::ethabi_decode::Token> {
+				::ethabi_decode::__private::Vec::from([
+					#( ::ethabi_decode::Tokenizable::into_token(self.#members) ),*
+				])
+			}
+		}
+	})
+}
+
+fn keccak256(data: &[u8]) -> [u8; 32] {
+	let mut result = [0u8; 32];
+	let mut sponge = Keccak::v256();
+	sponge.update(data);
+	sponge.finalize(&mut result);
+	result
+}
+
+/// Declares a `StaticEvent` from a human-readable signature, like
+/// `event!("Transfer(address indexed from, address indexed to, uint256 value)")`.
+///
+/// The signature is parsed and its topic hashed at compile time, so the result
+/// can initialise a `const` or `static`.
+#[proc_macro]
+pub fn event(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as LitStr);
+	expand_event(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand_event(input: &LitStr) -> syn::Result<TokenStream2> {
+	let value = input.value();
+	let event = signature::parse_event(&value).map_err(|e| syn::Error::new(input.span(), e))?;
+	let signature = signature::signature(event.name, event.inputs.iter().map(|p| &p.kind));
+	let topic = keccak256(signature.as_bytes());
+	let kinds = event.inputs.iter().map(|p| signature::static_kind(&p.kind));
+	let indexed = event.inputs.iter().map(|p| p.indexed);
+	let names = event.inputs.iter().map(signature::static_names);
+	let anonymous = event.anonymous;
+
+	Ok(quote! {
+		::ethabi_decode::StaticEvent {
+			signature: #signature,
+			topic: ::ethabi_decode::H256([#( #topic ),*]),
+			inputs: &[#( ::ethabi_decode::StaticParam { kind: #kinds, indexed: #indexed, names: #names } ),*],
+			anonymous: #anonymous,
+		}
+	})
+}
+
+/// Declares a `StaticFunction` from a human-readable signature, like
+/// `function!("function balanceOf(address owner) external view returns (uint256)")`.
+///
+/// The signature is parsed and its selector hashed at compile time, so the
+/// result can initialise a `const` or `static`.
+#[proc_macro]
+pub fn function(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as LitStr);
+	expand_function(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand_function(input: &LitStr) -> syn::Result<TokenStream2> {
+	let value = input.value();
+	let function = signature::parse_function(&value).map_err(|e| syn::Error::new(input.span(), e))?;
+	let signature = signature::signature(function.name, function.inputs.iter().map(|p| &p.kind));
+	let selector = &keccak256(signature.as_bytes())[..4];
+	let inputs = function.inputs.iter().map(signature::static_function_param);
+	let outputs = function.outputs.iter().map(signature::static_function_param);
+	let state_mutability = signature::state_mutability(function.state_mutability);
+
+	Ok(quote! {
+		::ethabi_decode::StaticFunction {
+			signature: #signature,
+			selector: [#( #selector ),*],
+			inputs: &[#( #inputs ),*],
+			outputs: &[#( #outputs ),*],
+			state_mutability: #state_mutability,
+		}
+	})
+}
@@ -0,0 +1,192 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Compile-time parsing of human-readable signatures, with the parser
+//! `ethabi-decode` uses at runtime.
+use ethabi_decode_syntax as syntax;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+pub use syntax::{Event, Function, Kind, Mutability, Param, SyntaxError};
+
+/// Expands to the matching `StaticParamKind`.
+pub fn static_kind(kind: &Kind) -> TokenStream {
+	let path = quote!(::ethabi_decode::StaticParamKind);
+	match kind {
+		Kind::Address => quote!(#path::Address),
+		Kind::Bool => quote!(#path::Bool),
+		Kind::String => quote!(#path::String),
+		Kind::Bytes => quote!(#path::Bytes),
+		Kind::Uint(bits) => quote!(#path::Uint(#bits)),
+		Kind::Int(bits) => quote!(#path::Int(#bits)),
+		Kind::FixedBytes(len) => quote!(#path::FixedBytes(#len)),
+		Kind::Array(element) => {
+			let element = static_kind(element);
+			quote!(#path::Array(&#element))
+		}
+		Kind::FixedArray(element, len) => {
+			let element = static_kind(element);
+			quote!(#path::FixedArray(&#element, #len))
+		}
+		Kind::Tuple(components) => {
+			let components = components.iter().map(|c| static_kind(&c.kind));
+			quote!(#path::Tuple(&[#( #components ),*]))
+		}
+	}
+}
+
+/// Expands to the `StaticParamNames` of a param, with the names of its tuple
+/// components, or of the components of the tuples of an array.
+pub fn static_names(param: &Param) -> TokenStream {
+	let name = match param.name {
+		Some(name) => quote!(::core::option::Option::Some(#name)),
+		None => quote!(::core::option::Option::None),
+	};
+	let mut kind = &param.kind;
+	while let Kind::Array(element) | Kind::FixedArray(element, _) = kind {
+		kind = element;
+	}
+	let components = match kind {
+		Kind::Tuple(components) => components.iter().map(static_names).collect(),
+		_ => Vec::new(),
+	};
+	quote!(::ethabi_decode::StaticParamNames { name: #name, components: &[#( #components ),*] })
+}
+
+/// Expands to the `StaticFunctionParam` of a function param.
+pub fn static_function_param(param: &Param) -> TokenStream {
+	let (kind, names) = (static_kind(&param.kind), static_names(param));
+	quote!(::ethabi_decode::StaticFunctionParam { kind: #kind, names: #names })
+}
+
+/// Expands to the matching `StateMutability`.
+pub fn state_mutability(state_mutability: Mutability) -> TokenStream {
+	let path = quote!(::ethabi_decode::StateMutability);
+	match state_mutability {
+		Mutability::Pure => quote!(#path::Pure),
+		Mutability::View => quote!(#path::View),
+		Mutability::NonPayable => quote!(#path::NonPayable),
+		Mutability::Payable => quote!(#path::Payable),
+	}
+}
+
+/// Returns the canonical signature, like `transfer(address,uint256)`.
+pub fn signature<'k, 's: 'k>(name: &str, kinds: impl IntoIterator<Item = &'k Kind<'s>>) -> String {
+	let mut signature = String::new();
+	// writing to a `String` cannot fail
+	let _ = syntax::write_signature(&mut signature, name, kinds);
+	signature
+}
+
+/// Describes a syntax error, like "invalid type at byte 9".
+fn message(error: SyntaxError) -> String {
+	match error {
+		SyntaxError::InvalidType(pos) => format!("invalid type at byte {}", pos),
+		SyntaxError::InvalidSignature(pos) => format!("invalid signature at byte {}", pos),
+		SyntaxError::LimitExceeded(pos) => format!("type nested too deep at byte {}", pos),
+	}
+}
+
+/// Parses a type like `(address,uint256)[]`.
+pub fn parse_kind(input: &str) -> Result<Kind<'_>, String> {
+	syntax::parse_kind(input).map_err(message)
+}
+
+/// Parses an event like `event Transfer(address indexed from, address indexed to, uint256 value)`.
+pub fn parse_event(input: &str) -> Result<Event<'_>, String> {
+	syntax::parse_event(input).map_err(message)
+}
+
+/// Parses a function like `function balanceOf(address owner) external view returns (uint256)`.
+pub fn parse_function(input: &str) -> Result<Function<'_>, String> {
+	syntax::parse_function(input).map_err(message)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		parse_event, parse_function, signature, state_mutability, static_kind, static_names, Kind, Mutability,
+	};
+	use quote::quote;
+
+	#[test]
+	fn test_parse_event() {
+		let event =
+			parse_event("event Settled(address indexed maker, (uint64 id, bytes32[2] proof)[] orders) anonymous")
+				.unwrap();
+		assert_eq!((event.name, event.anonymous), ("Settled", true));
+		assert_eq!(
+			signature(event.name, event.inputs.iter().map(|p| &p.kind)),
+			"Settled(address,(uint64,bytes32[2])[])"
+		);
+
+		let (maker, orders) = (&event.inputs[0], &event.inputs[1]);
+		assert_eq!((maker.kind.clone(), maker.indexed, maker.name), (Kind::Address, true, Some("maker")));
+		assert!(!orders.indexed);
+		let components = match &orders.kind {
+			Kind::Array(element) => match &**element {
+				Kind::Tuple(components) => components,
+				kind => panic!("unexpected element {}", kind),
+			},
+			kind => panic!("unexpected kind {}", kind),
+		};
+		assert_eq!(components.iter().map(|c| c.name).collect::<Vec<_>>(), [Some("id"), Some("proof")]);
+	}
+
+	#[test]
+	fn test_parse_function() {
+		let function = parse_function("function balanceOf(address owner) external view returns (uint)").unwrap();
+		assert_eq!(signature(function.name, function.inputs.iter().map(|p| &p.kind)), "balanceOf(address)");
+		assert_eq!(function.outputs[0].kind.to_string(), "uint256");
+		assert_eq!(function.state_mutability, Mutability::View);
+
+		let function = parse_function("deposit() payable").unwrap();
+		assert!(function.inputs.is_empty() && function.outputs.is_empty());
+		assert_eq!(function.state_mutability, Mutability::Payable);
+	}
+
+	#[test]
+	fn test_errors() {
+		assert_eq!(parse_event("Transfer(uint7)").unwrap_err(), "invalid type at byte 9");
+		assert_eq!(parse_event("Transfer(address indexed from").unwrap_err(), "invalid signature at byte 29");
+		assert_eq!(parse_function("transfer() returns (bool) forever").unwrap_err(), "invalid signature at byte 26");
+		assert_eq!(parse_function("(uint256)").unwrap_err(), "invalid signature at byte 0");
+
+		let nested = format!("Deep({}bool{})", "(".repeat(40), ")".repeat(40));
+		assert_eq!(parse_event(&nested).unwrap_err(), "type nested too deep at byte 37");
+		assert_eq!(parse_function("function(uint8[0])").unwrap_err(), "invalid signature at byte 8");
+	}
+
+	#[test]
+	fn test_tokens() {
+		let event = parse_event("Sent((address,bytes)[2], bool)").unwrap();
+		let expected = quote!(::ethabi_decode::StaticParamKind::FixedArray(
+			&::ethabi_decode::StaticParamKind::Tuple(&[
+				::ethabi_decode::StaticParamKind::Address,
+				::ethabi_decode::StaticParamKind::Bytes
+			]),
+			2usize
+		));
+		assert_eq!(static_kind(&event.inputs[0].kind).to_string(), expected.to_string());
+
+		let event = parse_event("Sent((address to, bytes)[] deposits)").unwrap();
+		let expected = quote!(::ethabi_decode::StaticParamNames {
+			name: ::core::option::Option::Some("deposits"),
+			components: &[
+				::ethabi_decode::StaticParamNames { name: ::core::option::Option::Some("to"), components: &[] },
+				::ethabi_decode::StaticParamNames { name: ::core::option::Option::None, components: &[] }
+			]
+		});
+		assert_eq!(static_names(&event.inputs[0]).to_string(), expected.to_string());
+
+		assert_eq!(
+			state_mutability(Mutability::NonPayable).to_string(),
+			quote!(::ethabi_decode::StateMutability::NonPayable).to_string()
+		);
+	}
+}
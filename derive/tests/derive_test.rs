@@ -0,0 +1,172 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use ethabi_decode::{
+	decode_as, encode, event, function, Address, ErrorKind, EthAbiType, EthEvent, Param, ParamKind, ParamNames,
+	PathSegment, StateMutability, StaticEvent, StaticFunction, StaticParamKind, Token, Tokenizable, H256, U256,
+};
+use hex_literal::hex;
+
+#[derive(Debug, Clone, PartialEq, EthAbiType)]
+struct Deposit {
+	sender: Address,
+	amount: U256,
+	payload: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, EthAbiType)]
+struct Batch(Vec<Deposit>, bool);
+
+#[derive(Debug, Clone, PartialEq, EthEvent)]
+struct Transfer {
+	#[indexed]
+	from: Address,
+	#[indexed]
+	to: Address,
+	value: U256,
+}
+
+#[derive(Debug, Clone, PartialEq, EthEvent)]
+#[event(name = "Sent", anonymous)]
+struct SentEvent {
+	#[indexed]
+	nonce: U256,
+	#[abi_type = "(address,uint,bytes)"]
+	deposit: Deposit,
+}
+
+#[derive(Debug, Clone, PartialEq, EthEvent)]
+struct Settled(#[indexed] H256, Vec<(Address, bool)>, [u8; 4], [U256; 2], Vec<u8>);
+
+fn deposit() -> Deposit {
+	Deposit { sender: Address::from([0x11u8; 20]), amount: 1000.into(), payload: b"abc".to_vec() }
+}
+
+#[test]
+fn test_abi_type_schema() {
+	assert_eq!(Deposit::param_kind().to_string(), "(address,uint256,bytes)");
+	assert_eq!(Batch::param_kind().to_string(), "((address,uint256,bytes)[],bool)");
+}
+
+#[test]
+fn test_abi_type_round_trip() {
+	let batch = Batch(vec![deposit(), deposit()], true);
+	let encoded = encode(&[batch.clone().into_token()]);
+
+	assert_eq!(decode_as::<(Batch,)>(&encoded).unwrap().0, batch);
+
+	// a struct can also stand for the list of params
+	let encoded =
+		encode(&[Token::Address([0x11u8; 20].into()), Token::Uint(1000.into()), Token::Bytes(b"abc".to_vec())]);
+	assert_eq!(decode_as::<Deposit>(&encoded).unwrap(), deposit());
+}
+
+#[test]
+fn test_abi_type_mismatch() {
+	let token = Token::Tuple(vec![Token::Address([0x11u8; 20].into()), Token::Bool(true), Token::Bytes(vec![])]);
+	let error = Deposit::from_token(token).unwrap_err();
+
+	assert_eq!(error.kind, ErrorKind::TypeMismatch);
+	assert_eq!(error.path.segments(), &[PathSegment::Component(1)]);
+	assert!(Deposit::from_token(Token::Tuple(vec![])).is_err());
+}
+
+#[test]
+fn test_event_definition() {
+	let event = Transfer::event();
+
+	assert_eq!(event.signature, "Transfer(address,address,uint256)");
+	assert_eq!(
+		event.inputs,
+		vec![
+			Param::named("from", ParamKind::Address, true),
+			Param::named("to", ParamKind::Address, true),
+			Param::named("value", ParamKind::Uint(256), false),
+		]
+	);
+	assert!(!event.anonymous);
+
+	let event = SentEvent::event();
+	assert_eq!(event.signature, "Sent(uint256,(address,uint256,bytes))");
+	assert!(event.anonymous);
+
+	// the signature and its topic are computed at compile time
+	const TOPIC: H256 = Transfer::TOPIC;
+	assert_eq!(TOPIC, H256(hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef")));
+	assert_eq!(SentEvent::SIGNATURE, "Sent(uint256,(address,uint256,bytes))");
+	assert_eq!(SentEvent::INPUTS[1].kind.to_param_kind(), <Deposit as Tokenizable>::param_kind());
+	assert_eq!(Transfer::EVENT.check_signature(), Ok(()));
+	assert_eq!(SentEvent::EVENT.check_signature(), Ok(()));
+
+	// the Solidity types of the fields are told from their Rust types
+	assert_eq!(Settled::SIGNATURE, "Settled(bytes32,(address,bool)[],bytes4,uint256[2],bytes)");
+	assert_eq!(Settled::EVENT.check_signature(), Ok(()));
+}
+
+#[test]
+fn test_event_round_trip() {
+	let transfer = Transfer { from: Address::from([0x11u8; 20]), to: Address::from([0x22u8; 20]), value: 5.into() };
+	let (topics, data) = transfer.clone().encode_log().unwrap();
+
+	assert_eq!(
+		topics,
+		vec![
+			H256(hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef")),
+			H256(hex!("0000000000000000000000001111111111111111111111111111111111111111")),
+			H256(hex!("0000000000000000000000002222222222222222222222222222222222222222")),
+		]
+	);
+	assert_eq!(Transfer::decode_log(topics, data).unwrap(), transfer);
+
+	let sent = SentEvent { nonce: 7.into(), deposit: deposit() };
+	let (topics, data) = sent.clone().encode_log().unwrap();
+	assert_eq!(topics.len(), 1);
+	assert_eq!(SentEvent::decode_log(topics, data).unwrap(), sent);
+}
+
+static TRANSFER: StaticEvent = event!("event Transfer(address indexed from, address indexed to, uint256 value)");
+
+const TRANSFER_CALL: StaticFunction =
+	function!("function transfer(address to, uint256 amount) external returns (bool)");
+
+#[test]
+fn test_static_event() {
+	assert_eq!(TRANSFER.signature, "Transfer(address,address,uint256)");
+	assert_eq!(TRANSFER.topic, H256(hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef")));
+	assert_eq!(TRANSFER.to_owned_event(), Transfer::event());
+	assert_eq!(TRANSFER.check_signature(), Ok(()));
+
+	let transfer = Transfer { from: Address::from([0x11u8; 20]), to: Address::from([0x22u8; 20]), value: 5.into() };
+	let (topics, data) = transfer.clone().encode_log().unwrap();
+	assert_eq!(Transfer::from_tokens(TRANSFER.decode(topics, data).unwrap()).unwrap(), transfer);
+
+	const SENT: StaticEvent =
+		event!("Sent(uint256 indexed nonce, (address sender, uint amount, bytes payload) deposit) anonymous");
+	// the derived event has no names for the components of the tuple
+	let mut expected = SentEvent::event();
+	expected.inputs[1].names.components =
+		vec![ParamNames::named("sender"), ParamNames::named("amount"), ParamNames::named("payload")];
+	assert_eq!(SENT.to_owned_event(), expected);
+}
+
+#[test]
+fn test_static_function() {
+	assert_eq!(TRANSFER_CALL.signature, "transfer(address,uint256)");
+	assert_eq!(TRANSFER_CALL.selector, hex!("a9059cbb"));
+	assert_eq!(TRANSFER_CALL.outputs[0].kind, StaticParamKind::Bool);
+	assert_eq!(TRANSFER_CALL.state_mutability, StateMutability::NonPayable);
+	assert_eq!(TRANSFER_CALL.inputs[1].name(), Some("amount"));
+	assert_eq!(TRANSFER_CALL.check_signature(), Ok(()));
+
+	const SUBMIT: StaticFunction = function!("submit((bytes32,uint64)[2][] proofs, bytes calldata) payable");
+	assert_eq!(SUBMIT.signature, "submit((bytes32,uint64)[2][],bytes)");
+	assert_eq!(SUBMIT.to_owned_function().inputs[0].kind.to_string(), "(bytes32,uint64)[2][]");
+	assert_eq!(SUBMIT.inputs[0].name(), Some("proofs"));
+	assert!(SUBMIT.inputs[0].names.components.iter().all(|c| c.name.is_none()));
+	assert_eq!(SUBMIT.state_mutability, StateMutability::Payable);
+}
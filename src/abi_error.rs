@@ -0,0 +1,225 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Custom errors, as declared with `error` since Solidity 0.8.4.
+use crate::std::{BTreeMap, String, Vec};
+
+use crate::{
+	decoder::decode_shapes,
+	encoder::encode_shapes,
+	param::{find_token, kinds},
+	signature,
+	util::keccak256,
+	DecodeLimits, DecodedParams, Error, ErrorKind, FunctionParam, Token,
+};
+
+/// Custom error, reverted with like a function is called: its selector followed
+/// by its encoded params.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AbiError<'a> {
+	/// Error signature. Like "InsufficientBalance(uint256,uint256)".
+	pub signature: &'a str,
+	/// Error params.
+	pub inputs: &'a [FunctionParam],
+}
+
+impl<'a> AbiError<'a> {
+	/// Returns the first four bytes of the signature hash, which prefix the revert data.
+	pub fn selector(&self) -> [u8; 4] {
+		let mut selector = [0u8; 4];
+		selector.copy_from_slice(&keccak256(self.signature.as_bytes())[..4]);
+		selector
+	}
+
+	/// Returns the name of the error, see `ethabi_decode::name`.
+	pub fn name(&self) -> &'a str {
+		signature::name(self.signature)
+	}
+
+	/// Checks that `signature` is the canonical signature for `inputs`, see `ethabi_decode::check_signature`.
+	pub fn check_signature(&self) -> Result<(), Error> {
+		signature::check_signature(self.signature, kinds(self.inputs))
+	}
+
+	/// Encodes the revert data for the error with the given tokens, checking
+	/// them against `inputs` like `encode_params`.
+	pub fn encode(&self, tokens: &[Token]) -> Result<Vec<u8>, Error> {
+		let mut data = self.selector().to_vec();
+		data.extend(encode_shapes(kinds(self.inputs), tokens)?);
+		Ok(data)
+	}
+
+	/// Decodes revert data, checking that it starts with the error selector.
+	pub fn decode(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+		match data.split_first_chunk::<4>() {
+			Some((selector, params)) if *selector == self.selector() => {
+				decode_shapes(kinds(self.inputs), params, &DecodeLimits::default(), false)
+			}
+			_ => Err(ErrorKind::SelectorMismatch.into()),
+		}
+	}
+
+	/// Decodes revert data like `decode`, keeping the name and type of each value.
+	pub fn decode_named(&self, data: &[u8]) -> Result<DecodedParams, Error> {
+		Ok(DecodedParams::from_function(self.inputs, self.decode(data)?))
+	}
+
+	/// Returns the param named `name` among tokens decoded by `decode`, see `Function::find_input`.
+	pub fn find_input<'t>(&self, tokens: &'t [Token], name: &str) -> Option<&'t Token> {
+		find_token(self.inputs.iter().map(|p| &p.names), tokens, name)
+	}
+}
+
+/// Custom error owning its definition, with the signature derived from its name and inputs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnedAbiError {
+	/// Error signature. Like "InsufficientBalance(uint256,uint256)".
+	pub signature: String,
+	/// Error params.
+	pub inputs: Vec<FunctionParam>,
+}
+
+impl OwnedAbiError {
+	/// Creates an error, computing its canonical signature from `name` and `inputs`.
+	pub fn new(name: &str, inputs: Vec<FunctionParam>) -> Self {
+		let signature = signature::signature(name, kinds(&inputs));
+		OwnedAbiError { signature, inputs }
+	}
+
+	/// Borrows the error, to encode and decode revert data with it.
+	pub fn as_error(&self) -> AbiError<'_> {
+		AbiError { signature: &self.signature, inputs: &self.inputs }
+	}
+}
+
+/// Set of known custom errors, looked up by selector to find which one revert data holds.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ErrorRegistry {
+	errors: BTreeMap<[u8; 4], OwnedAbiError>,
+}
+
+impl ErrorRegistry {
+	/// Creates an empty registry.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds an error, replacing the one with the same selector if any.
+	pub fn insert(&mut self, error: OwnedAbiError) {
+		self.errors.insert(error.as_error().selector(), error);
+	}
+
+	/// Returns the error with the given selector.
+	pub fn get(&self, selector: [u8; 4]) -> Option<&OwnedAbiError> {
+		self.errors.get(&selector)
+	}
+
+	/// Decodes revert data with the error whose selector it starts with, returning
+	/// that error along with the decoded tokens.
+	///
+	/// Fails with `ErrorKind::SelectorMismatch` if no known error matches.
+	pub fn decode(&self, data: &[u8]) -> Result<(&OwnedAbiError, Vec<Token>), Error> {
+		let (selector, params) = data.split_first_chunk::<4>().ok_or(ErrorKind::SelectorMismatch)?;
+		let error = self.get(*selector).ok_or(ErrorKind::SelectorMismatch)?;
+		Ok((error, decode_shapes(kinds(&error.inputs), params, &DecodeLimits::default(), false)?))
+	}
+
+	/// Decodes revert data like `decode`, keeping the name and type of each value.
+	pub fn decode_named(&self, data: &[u8]) -> Result<(&OwnedAbiError, DecodedParams), Error> {
+		let (error, tokens) = self.decode(data)?;
+		Ok((error, DecodedParams::from_function(&error.inputs, tokens)))
+	}
+}
+
+impl FromIterator<OwnedAbiError> for ErrorRegistry {
+	fn from_iter<I: IntoIterator<Item = OwnedAbiError>>(errors: I) -> Self {
+		let mut registry = ErrorRegistry::new();
+		errors.into_iter().for_each(|error| registry.insert(error));
+		registry
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{AbiError, ErrorRegistry, OwnedAbiError};
+	use crate::{ErrorKind, FunctionParam, ParamKind, PathSegment, Token};
+	use hex_literal::hex;
+
+	const INSUFFICIENT_BALANCE: AbiError<'static> = AbiError {
+		signature: "InsufficientBalance(uint256,uint256)",
+		inputs: &[FunctionParam::new(ParamKind::Uint(256)), FunctionParam::new(ParamKind::Uint(256))],
+	};
+
+	#[test]
+	fn test_round_trip() {
+		assert_eq!(INSUFFICIENT_BALANCE.selector(), hex!("cf479181"));
+		assert_eq!(INSUFFICIENT_BALANCE.check_signature(), Ok(()));
+
+		let tokens = vec![Token::Uint(1.into()), Token::Uint(2.into())];
+		let data = INSUFFICIENT_BALANCE.encode(&tokens).unwrap();
+		assert_eq!(data[..4], hex!("cf479181"));
+		assert_eq!(INSUFFICIENT_BALANCE.decode(&data).unwrap(), tokens);
+
+		let error = OwnedAbiError::new("Unauthorized", vec![]);
+		assert_eq!(
+			INSUFFICIENT_BALANCE.decode(&error.as_error().encode(&[]).unwrap()).unwrap_err().kind,
+			ErrorKind::SelectorMismatch
+		);
+		assert_eq!(INSUFFICIENT_BALANCE.decode(&[]).unwrap_err().kind, ErrorKind::SelectorMismatch);
+
+		let error = INSUFFICIENT_BALANCE.encode(&[Token::Uint(1.into()), Token::Bool(true)]).unwrap_err();
+		assert_eq!((error.kind, error.path.segments()), (ErrorKind::TypeMismatch, &[PathSegment::Input(1)][..]));
+	}
+
+	#[test]
+	fn test_registry() {
+		let registry = [
+			OwnedAbiError::new(
+				"InsufficientBalance",
+				vec![FunctionParam::new(ParamKind::Uint(256)), FunctionParam::new(ParamKind::Uint(256))],
+			),
+			OwnedAbiError::new("Unauthorized", vec![FunctionParam::new(ParamKind::Address)]),
+		]
+		.into_iter()
+		.collect::<ErrorRegistry>();
+
+		let data = INSUFFICIENT_BALANCE.encode(&[Token::Uint(1.into()), Token::Uint(2.into())]).unwrap();
+		let (error, tokens) = registry.decode(&data).unwrap();
+		assert_eq!(error.signature, "InsufficientBalance(uint256,uint256)");
+		assert_eq!(tokens, vec![Token::Uint(1.into()), Token::Uint(2.into())]);
+
+		let unknown = OwnedAbiError::new("Paused", vec![]);
+		assert_eq!(
+			registry.decode(&unknown.as_error().encode(&[]).unwrap()).unwrap_err().kind,
+			ErrorKind::SelectorMismatch
+		);
+		assert_eq!(registry.decode(&hex!("cf4791")).unwrap_err().kind, ErrorKind::SelectorMismatch);
+		assert_eq!(registry.decode(&data[..36]).unwrap_err().kind, ErrorKind::OutOfBounds);
+	}
+
+	#[test]
+	fn test_names() {
+		let error: OwnedAbiError = "error InsufficientBalance(uint256 available, uint256 required)".parse().unwrap();
+		assert_eq!(error.as_error().signature, INSUFFICIENT_BALANCE.signature);
+		let names = error.inputs.iter().map(FunctionParam::name).collect::<Vec<_>>();
+		assert_eq!(names, [Some("available"), Some("required")]);
+
+		let tokens = vec![Token::Uint(1.into()), Token::Uint(2.into())];
+		let data = INSUFFICIENT_BALANCE.encode(&tokens).unwrap();
+		assert_eq!(error.as_error().find_input(&tokens, "required"), Some(&tokens[1]));
+		assert_eq!(INSUFFICIENT_BALANCE.find_input(&tokens, "required"), None);
+
+		let decoded = error.as_error().decode_named(&data).unwrap();
+		assert_eq!(decoded.get("available").unwrap().token, tokens[0]);
+
+		let registry = [error].into_iter().collect::<ErrorRegistry>();
+		let (error, decoded) = registry.decode_named(&data).unwrap();
+		assert_eq!(error.as_error().name(), "InsufficientBalance");
+		assert_eq!(decoded.get("required").unwrap().token, tokens[1]);
+	}
+}
@@ -0,0 +1,310 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Contract ABI, with its definitions looked up by selector and topic.
+use crate::std::{BTreeMap, Vec};
+use crate::{
+	decoder::decode_shapes,
+	encoder::encode_shapes,
+	param::{find_token, kinds},
+	DecodeLimits, DecodedLog, DecodedParams, Error, ErrorKind, ErrorRegistry, EventRegistry, FunctionParam,
+	OwnedAbiError, OwnedEvent, OwnedFunction, StateMutability, Token, H256,
+};
+
+/// Contract constructor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Constructor {
+	/// Constructor input.
+	pub inputs: Vec<FunctionParam>,
+	/// Constructor state mutability.
+	pub state_mutability: StateMutability,
+}
+
+impl Constructor {
+	/// Encodes the constructor arguments, which follow the bytecode in a deployment,
+	/// checking them against `inputs` like `encode_params`.
+	pub fn encode_input(&self, tokens: &[Token]) -> Result<Vec<u8>, Error> {
+		encode_shapes(kinds(&self.inputs), tokens)
+	}
+
+	/// Decodes the constructor arguments, once split from the bytecode.
+	pub fn decode_input(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+		decode_shapes(kinds(&self.inputs), data, &DecodeLimits::default(), false)
+	}
+
+	/// Decodes the constructor arguments like `decode_input`, keeping the name and type of each value.
+	pub fn decode_input_named(&self, data: &[u8]) -> Result<DecodedParams, Error> {
+		Ok(DecodedParams::from_function(&self.inputs, self.decode_input(data)?))
+	}
+
+	/// Returns the input named `name` among tokens decoded by `decode_input`, see `Function::find_input`.
+	pub fn find_input<'t>(&self, tokens: &'t [Token], name: &str) -> Option<&'t Token> {
+		find_token(self.inputs.iter().map(|p| &p.names), tokens, name)
+	}
+}
+
+/// Functions, events, errors and constructor of a contract.
+///
+/// Functions and errors are indexed by selector and events by their signature
+/// topic, so calls, logs and reverts find their definition in `O(log n)`. Logs
+/// of anonymous events are matched as described in `EventRegistry::decode`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Contract {
+	constructor: Option<Constructor>,
+	functions: BTreeMap<[u8; 4], OwnedFunction>,
+	events: EventRegistry,
+	errors: ErrorRegistry,
+}
+
+impl Contract {
+	/// Creates a contract without any definitions.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the constructor.
+	pub fn set_constructor(&mut self, constructor: Constructor) {
+		self.constructor = Some(constructor);
+	}
+
+	/// Adds a function, replacing the one with the same selector if any.
+	pub fn add_function(&mut self, function: OwnedFunction) {
+		self.functions.insert(function.as_function().selector(), function);
+	}
+
+	/// Adds an event, replacing the one with the same topic if any, see `EventRegistry::insert`.
+	pub fn add_event(&mut self, event: OwnedEvent) {
+		self.events.insert(event);
+	}
+
+	/// Adds a custom error, replacing the one with the same selector if any.
+	pub fn add_error(&mut self, error: OwnedAbiError) {
+		self.errors.insert(error);
+	}
+
+	/// Returns the constructor, if the contract declares one.
+	pub fn constructor(&self) -> Option<&Constructor> {
+		self.constructor.as_ref()
+	}
+
+	/// Returns the function with the given selector.
+	pub fn function(&self, selector: [u8; 4]) -> Option<&OwnedFunction> {
+		self.functions.get(&selector)
+	}
+
+	/// Returns the non-anonymous event with the given signature topic.
+	pub fn event(&self, topic: &H256) -> Option<&OwnedEvent> {
+		self.events.get(topic)
+	}
+
+	/// Returns the custom error with the given selector.
+	pub fn error(&self, selector: [u8; 4]) -> Option<&OwnedAbiError> {
+		self.errors.get(selector)
+	}
+
+	/// Returns all the functions, ordered by selector.
+	pub fn functions(&self) -> impl Iterator<Item = &OwnedFunction> {
+		self.functions.values()
+	}
+
+	/// Returns all the events, the anonymous ones last.
+	pub fn events(&self) -> impl Iterator<Item = &OwnedEvent> {
+		self.events.iter()
+	}
+
+	/// Decodes calldata with the function its selector matches, returning the
+	/// function name along with the decoded inputs.
+	///
+	/// Fails with `ErrorKind::SelectorMismatch` if no function matches.
+	pub fn decode_call(&self, calldata: &[u8]) -> Result<(&str, Vec<Token>), Error> {
+		let selector = calldata.first_chunk::<4>().ok_or(ErrorKind::SelectorMismatch)?;
+		let function = self.function(*selector).ok_or(ErrorKind::SelectorMismatch)?.as_function();
+		Ok((function.name(), function.decode_input(calldata)?))
+	}
+
+	/// Decodes calldata like `decode_call`, keeping the name and type of each input.
+	pub fn decode_call_named(&self, calldata: &[u8]) -> Result<(&str, DecodedParams), Error> {
+		let selector = calldata.first_chunk::<4>().ok_or(ErrorKind::SelectorMismatch)?;
+		let function = self.function(*selector).ok_or(ErrorKind::SelectorMismatch)?.as_function();
+		Ok((function.name(), function.decode_input_named(calldata)?))
+	}
+
+	/// Decodes a log with the event it was emitted for, returning the event name
+	/// along with the decoded inputs, see `EventRegistry::decode`.
+	pub fn decode_log(&self, topics: Vec<H256>, data: Vec<u8>) -> Result<(&str, Vec<Token>), Error> {
+		let (event, tokens) = self.events.decode(topics, data)?;
+		Ok((event.as_event().name(), tokens))
+	}
+
+	/// Decodes a log like `decode_log`, keeping the names of the values, see `Event::decode_named`.
+	pub fn decode_log_named(&self, topics: Vec<H256>, data: Vec<u8>) -> Result<DecodedLog, Error> {
+		self.events.decode_named(topics, data)
+	}
+
+	/// Decodes revert data with the custom error its selector matches, returning
+	/// the error name along with the decoded params, see `ErrorRegistry::decode`.
+	pub fn decode_error(&self, data: &[u8]) -> Result<(&str, Vec<Token>), Error> {
+		let (error, tokens) = self.errors.decode(data)?;
+		Ok((error.as_error().name(), tokens))
+	}
+
+	/// Decodes revert data like `decode_error`, keeping the name and type of each param.
+	pub fn decode_error_named(&self, data: &[u8]) -> Result<(&str, DecodedParams), Error> {
+		let (error, params) = self.errors.decode_named(data)?;
+		Ok((error.as_error().name(), params))
+	}
+}
+
+/// Collects the entries of a JSON ABI. Fallback and receive functions are
+/// left out, as they have no params to decode.
+#[cfg(feature = "serde")]
+impl FromIterator<crate::AbiItem> for Contract {
+	fn from_iter<I: IntoIterator<Item = crate::AbiItem>>(items: I) -> Self {
+		use crate::AbiItem;
+
+		let mut contract = Contract::new();
+		for item in items {
+			match item {
+				AbiItem::Function(function) => contract.add_function(function),
+				AbiItem::Event(event) => contract.add_event(event),
+				AbiItem::Error(error) => contract.add_error(error),
+				AbiItem::Constructor { inputs, state_mutability } => {
+					contract.set_constructor(Constructor { inputs, state_mutability: state_mutability.0 })
+				}
+				AbiItem::Fallback { .. } | AbiItem::Receive { .. } => {}
+			}
+		}
+		contract
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Constructor, Contract};
+	use crate::{
+		ErrorKind, FunctionParam, OwnedAbiError, OwnedEvent, OwnedFunction, Param, ParamKind, StateMutability, Token,
+		H256,
+	};
+	use hex_literal::hex;
+
+	fn contract() -> Contract {
+		let mut contract = Contract::new();
+		contract.set_constructor(Constructor {
+			inputs: vec![FunctionParam::named("owner", ParamKind::Address)],
+			state_mutability: StateMutability::NonPayable,
+		});
+		contract.add_function("function transfer(address to, uint256 amount) returns (bool)".parse().unwrap());
+		contract.add_function("function balanceOf(address owner) view returns (uint256)".parse().unwrap());
+		contract.add_event("event Transfer(address indexed from, address indexed to, uint256 value)".parse().unwrap());
+		contract.add_event("event Sent(uint256 nonce) anonymous".parse().unwrap());
+		contract.add_error("error InsufficientBalance(uint256 available, uint256 required)".parse().unwrap());
+		contract
+	}
+
+	#[test]
+	fn test_lookup() {
+		let contract = contract();
+
+		assert_eq!(contract.function(hex!("a9059cbb")).unwrap().signature, "transfer(address,uint256)");
+		assert!(contract.function(hex!("00000000")).is_none());
+		let topic = H256(hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"));
+		assert_eq!(contract.event(&topic).unwrap().signature, "Transfer(address,address,uint256)");
+		assert_eq!(contract.error(hex!("cf479181")).unwrap().signature, "InsufficientBalance(uint256,uint256)");
+		assert_eq!(contract.functions().count(), 2);
+		assert_eq!(contract.events().last().unwrap().signature, "Sent(uint256)");
+		assert_eq!(contract.constructor().unwrap().inputs[0].kind, ParamKind::Address);
+	}
+
+	#[test]
+	fn test_constructor() {
+		let contract = contract();
+		let constructor = contract.constructor().unwrap();
+		let tokens = vec![Token::Address([0x11u8; 20].into())];
+
+		let data = constructor.encode_input(&tokens).unwrap();
+		assert_eq!(constructor.decode_input(&data).unwrap(), tokens);
+		assert_eq!(constructor.decode_input_named(&data).unwrap().get("owner").unwrap().token, tokens[0]);
+		assert_eq!(constructor.find_input(&tokens, "owner"), Some(&tokens[0]));
+		assert_eq!(constructor.encode_input(&[Token::Bool(true)]).unwrap_err().kind, ErrorKind::TypeMismatch);
+		assert_eq!(constructor.encode_input(&[]).unwrap_err().kind, ErrorKind::TypeMismatch);
+	}
+
+	#[test]
+	fn test_decode_call() {
+		let contract = contract();
+		let tokens = vec![Token::Address([0x11u8; 20].into()), Token::Uint(5.into())];
+		let calldata = contract.function(hex!("a9059cbb")).unwrap().as_function().encode_input(&tokens).unwrap();
+
+		assert_eq!(contract.decode_call(&calldata).unwrap(), ("transfer", tokens));
+		assert_eq!(contract.decode_call(&hex!("deadbeef")).unwrap_err().kind, ErrorKind::SelectorMismatch);
+		assert_eq!(contract.decode_call(&[]).unwrap_err().kind, ErrorKind::SelectorMismatch);
+	}
+
+	#[test]
+	fn test_decode_log() {
+		let contract = contract();
+		let transfer: OwnedEvent = "Transfer(address indexed,address indexed,uint256)".parse().unwrap();
+		let tokens =
+			vec![Token::Address([0x11u8; 20].into()), Token::Address([0x22u8; 20].into()), Token::Uint(5.into())];
+		let (topics, data) = transfer.as_event().encode_log(&tokens).unwrap();
+
+		assert_eq!(contract.decode_log(topics, data.clone()).unwrap(), ("Transfer", tokens));
+		// without topics, the data is that of a log of the anonymous event
+		assert_eq!(contract.decode_log(vec![], data.clone()).unwrap(), ("Sent", vec![Token::Uint(5.into())]));
+		assert_eq!(contract.decode_log(vec![H256::zero()], data).unwrap_err().kind, ErrorKind::SignatureMismatch);
+	}
+
+	#[test]
+	fn test_decode_named() {
+		let contract = contract();
+		let tokens = vec![Token::Address([0x11u8; 20].into()), Token::Uint(5.into())];
+		let calldata = contract.function(hex!("a9059cbb")).unwrap().as_function().encode_input(&tokens).unwrap();
+		let (name, inputs) = contract.decode_call_named(&calldata).unwrap();
+		assert_eq!((name, inputs.find("amount")), ("transfer", Some(&tokens[1])));
+
+		let transfer = contract.events().next().unwrap().as_event();
+		let tokens =
+			vec![Token::Address([0x11u8; 20].into()), Token::Address([0x22u8; 20].into()), Token::Uint(5.into())];
+		let (topics, data) = transfer.encode_log(&tokens).unwrap();
+		let log = contract.decode_log_named(topics, data.clone()).unwrap();
+		assert_eq!(log.signature, "Transfer(address,address,uint256)");
+		assert_eq!(log.get("to").unwrap().token, tokens[1]);
+		assert!(log.get("to").unwrap().indexed);
+
+		let log = contract.decode_log_named(vec![], data).unwrap();
+		assert_eq!(log.name(), "Sent");
+		assert_eq!(log.find("nonce"), Some(&Token::Uint(5.into())));
+	}
+
+	#[test]
+	fn test_decode_error() {
+		let contract = contract();
+		let error: OwnedAbiError = "InsufficientBalance(uint256,uint256)".parse().unwrap();
+		let tokens = vec![Token::Uint(1.into()), Token::Uint(2.into())];
+
+		let data = error.as_error().encode(&tokens).unwrap();
+
+		let (name, params) = contract.decode_error_named(&data).unwrap();
+		assert_eq!((name, &params.get("required").unwrap().token), ("InsufficientBalance", &tokens[1]));
+		assert_eq!(contract.decode_error(&data).unwrap(), ("InsufficientBalance", tokens));
+
+		let unknown = OwnedFunction::new("Paused", vec![], vec![], StateMutability::NonPayable);
+		let data = unknown.as_function().encode_input(&[]).unwrap();
+		assert_eq!(contract.decode_error(&data).unwrap_err().kind, ErrorKind::SelectorMismatch);
+	}
+
+	#[test]
+	fn test_decode_anonymous_log() {
+		let mut contract = Contract::new();
+		contract.add_event(OwnedEvent::new("Sent", vec![Param::new(ParamKind::Uint(256), true)], true));
+
+		let topics = vec![H256::from_low_u64_be(7)];
+		assert_eq!(contract.decode_log(topics, vec![]).unwrap(), ("Sent", vec![Token::Uint(7.into())]));
+		assert_eq!(contract.decode_log(vec![], vec![]).unwrap_err().kind, ErrorKind::SignatureMismatch);
+	}
+}
@@ -0,0 +1,199 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Decoded values along with the params they were decoded for.
+use crate::std::{String, Vec};
+
+use crate::{event::is_hashed_topic, param::find_token, signature, FunctionParam, Param, ParamKind, ParamNames, Token};
+
+/// Value decoded for a param, with the name and type of the param.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedParam {
+	/// Names of the param and of its tuple components.
+	pub names: ParamNames,
+	/// Param type.
+	pub kind: ParamKind,
+	/// Whether the value was read from a log topic rather than from the data.
+	pub indexed: bool,
+	/// Whether the value is only the hash of the one logged. Indexed `string`,
+	/// `bytes`, arrays and tuples are logged as the keccak256 hash of their
+	/// value, which `token` holds as `bytes32` instead of a value of `kind`.
+	pub hashed: bool,
+	/// Decoded value.
+	pub token: Token,
+}
+
+impl DecodedParam {
+	/// Returns the name of the param, if it has one.
+	pub fn name(&self) -> Option<&str> {
+		self.names.name.as_deref()
+	}
+}
+
+/// Values decoded for a list of params, in the order of the params.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DecodedParams(Vec<DecodedParam>);
+
+impl DecodedParams {
+	/// Pairs the tokens decoded for event params with those params.
+	pub(crate) fn from_event(params: &[Param], tokens: Vec<Token>) -> Self {
+		let params = params.iter().zip(tokens).map(|(param, token)| DecodedParam {
+			names: param.names.clone(),
+			kind: param.kind.clone(),
+			indexed: param.indexed,
+			hashed: param.indexed && is_hashed_topic(&param.kind),
+			token,
+		});
+		DecodedParams(params.collect())
+	}
+
+	/// Pairs the tokens decoded for function, constructor or error params with those params.
+	pub(crate) fn from_function(params: &[FunctionParam], tokens: Vec<Token>) -> Self {
+		let params = params.iter().zip(tokens).map(|(param, token)| DecodedParam {
+			names: param.names.clone(),
+			kind: param.kind.clone(),
+			indexed: false,
+			hashed: false,
+			token,
+		});
+		DecodedParams(params.collect())
+	}
+
+	/// Returns the param named `name`.
+	pub fn get(&self, name: &str) -> Option<&DecodedParam> {
+		self.0.iter().find(|param| param.name() == Some(name))
+	}
+
+	/// Returns the value named `name`, a dotted name like "order.amount"
+	/// stepping into tuple components.
+	pub fn find(&self, name: &str) -> Option<&Token> {
+		let (first, rest) = match name.split_once('.') {
+			Some((first, rest)) => (first, Some(rest)),
+			None => (name, None),
+		};
+		let param = self.get(first)?;
+		match (rest, &param.token) {
+			(None, token) => Some(token),
+			(Some(rest), Token::Tuple(components)) => find_token(&param.names.components, components, rest),
+			(Some(_), _) => None,
+		}
+	}
+
+	/// Returns the decoded params in order.
+	pub fn iter(&self) -> core::slice::Iter<'_, DecodedParam> {
+		self.0.iter()
+	}
+
+	/// Returns the number of params.
+	pub fn len(&self) -> usize {
+		self.0.len()
+	}
+
+	/// Returns whether there are no params.
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// Returns the decoded values in order, as returned by `Event::decode`.
+	pub fn into_tokens(self) -> Vec<Token> {
+		self.0.into_iter().map(|param| param.token).collect()
+	}
+}
+
+impl<'a> IntoIterator for &'a DecodedParams {
+	type Item = &'a DecodedParam;
+	type IntoIter = core::slice::Iter<'a, DecodedParam>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter()
+	}
+}
+
+/// Decoded event log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedLog {
+	/// Signature of the event the log was emitted for. Like "Transfer(address,address,uint256)".
+	pub signature: String,
+	/// Values of the event inputs.
+	pub params: DecodedParams,
+}
+
+impl DecodedLog {
+	/// Returns the name of the event, see `ethabi_decode::name`.
+	pub fn name(&self) -> &str {
+		signature::name(&self.signature)
+	}
+
+	/// Returns the input named `name`, see `DecodedParams::get`.
+	pub fn get(&self, name: &str) -> Option<&DecodedParam> {
+		self.params.get(name)
+	}
+
+	/// Returns the value named `name`, see `DecodedParams::find`.
+	pub fn find(&self, name: &str) -> Option<&Token> {
+		self.params.find(name)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{util::keccak256, OwnedEvent, OwnedFunction, ParamKind, Token, H256};
+
+	#[test]
+	fn test_decode_named() {
+		let event: OwnedEvent =
+			"event Posted(address indexed author, string indexed tag, (uint64 id, string body) post, bool)"
+				.parse()
+				.unwrap();
+		let event = event.as_event();
+		let post = Token::Tuple(vec![Token::Uint(3.into()), Token::String(b"hello".to_vec())]);
+		let tokens =
+			vec![Token::Address([0x11u8; 20].into()), Token::String(b"news".to_vec()), post.clone(), Token::Bool(true)];
+		let (topics, data) = event.encode_log(&tokens).unwrap();
+
+		let log = event.decode_named(topics, data).unwrap();
+		assert_eq!(log.name(), "Posted");
+		assert_eq!(log.params.len(), 4);
+
+		let author = log.get("author").unwrap();
+		assert_eq!((author.kind.clone(), author.indexed, author.hashed), (ParamKind::Address, true, false));
+		assert_eq!(author.token, tokens[0]);
+
+		let tag = log.get("tag").unwrap();
+		assert_eq!((tag.kind.clone(), tag.indexed, tag.hashed), (ParamKind::String, true, true));
+		assert_eq!(tag.token, Token::FixedBytes(H256::from(keccak256(b"news")).as_bytes().to_vec()));
+
+		let decoded = log.get("post").unwrap();
+		assert!(!decoded.indexed && !decoded.hashed);
+		assert_eq!(decoded.token, post);
+		assert_eq!(log.find("post.body"), Some(&Token::String(b"hello".to_vec())));
+		assert_eq!(log.find("post.title"), None);
+		assert_eq!(log.find("author.id"), None);
+
+		// the unnamed bool can only be reached by position
+		assert_eq!(log.params.iter().last().unwrap().name(), None);
+		assert_eq!(log.params.into_tokens()[3], Token::Bool(true));
+	}
+
+	#[test]
+	fn test_decode_function_named() {
+		let function: OwnedFunction = "function transfer(address to, uint256 amount) returns (bool)".parse().unwrap();
+		let function = function.as_function();
+		let tokens = vec![Token::Address([0x22u8; 20].into()), Token::Uint(5.into())];
+
+		let inputs = function.decode_input_named(&function.encode_input(&tokens).unwrap()).unwrap();
+		assert_eq!(inputs.get("to").unwrap().token, tokens[0]);
+		assert_eq!(inputs.find("amount"), Some(&tokens[1]));
+		assert!(inputs.iter().all(|param| !param.indexed && !param.hashed));
+
+		let outputs = function.decode_output_named(&function.encode_output(&[Token::Bool(true)]).unwrap()).unwrap();
+		assert_eq!(outputs.len(), 1);
+		assert_eq!(outputs.get(""), None);
+		assert_eq!(outputs.iter().next().unwrap().token, Token::Bool(true));
+	}
+}
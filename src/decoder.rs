@@ -9,23 +9,108 @@
 
 //! ABI decoder.
 
-use crate::{util::slice_data, Error, ParamKind, Token, Word};
+use crate::{
+	param::{is_dynamic, is_empty_bytes_valid_encoding, is_valid_int_width},
+	util::{fits_int, fits_uint, slice_data, Words},
+	Error, ErrorKind, ParamKind, PathSegment, Token, TokenRef, Word,
+};
+use ethabi_decode_syntax::{Shape, TypeShape};
 
 use crate::std::Vec;
+use core::mem::size_of;
 
-struct DecodeResult {
-	token: Token,
+/// Upper bounds enforced while decoding untrusted data.
+///
+/// Lengths inside ABI-encoded data are attacker controlled, so a small payload
+/// could otherwise make the decoder allocate far more memory than it was given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+	/// Maximum number of bytes the decoded tokens may occupy, counting the input
+	/// and every byte of bytes and strings, whether they are copied or borrowed.
+	pub max_allocation: usize,
+	/// Maximum number of elements in a single dynamic array.
+	pub max_array_length: usize,
+	/// Maximum nesting of arrays and tuples.
+	pub max_depth: usize,
+}
+
+impl Default for DecodeLimits {
+	fn default() -> Self {
+		DecodeLimits { max_allocation: 16 * 1024 * 1024, max_array_length: 1 << 20, max_depth: 32 }
+	}
+}
+
+/// State shared by all the params of a single decode: how much of the
+/// `DecodeLimits` has been used up and whether canonical encoding is enforced.
+struct DecodeContext<'a> {
+	limits: &'a DecodeLimits,
+	allocated: usize,
+	strict: bool,
+	/// Number of words in the whole input. Every slice handed to `decode_param`
+	/// is a suffix of the input, which gives back absolute positions for errors.
+	words: usize,
+}
+
+impl<'a> DecodeContext<'a> {
+	fn new(limits: &'a DecodeLimits, strict: bool) -> Self {
+		DecodeContext { limits, allocated: 0, strict, words: 0 }
+	}
+
+	/// Creates an error located at word `position` of `slices`.
+	fn error(&self, kind: ErrorKind, slices: Words, position: usize) -> Error {
+		let word = (self.words - slices.len()).saturating_add(position);
+		Error::at(kind, word.saturating_mul(32))
+	}
+
+	/// Accounts for `bytes` more bytes, failing before the allocation is made if the limit would be exceeded.
+	fn charge(&mut self, bytes: usize) -> Result<(), ErrorKind> {
+		match self.allocated.checked_add(bytes) {
+			Some(allocated) if allocated <= self.limits.max_allocation => {
+				self.allocated = allocated;
+				Ok(())
+			}
+			_ => Err(ErrorKind::LimitExceeded),
+		}
+	}
+
+	/// Accounts for `count` tokens.
+	fn charge_tokens(&mut self, count: usize) -> Result<(), ErrorKind> {
+		self.charge(count.checked_mul(size_of::<Token>()).ok_or(ErrorKind::LimitExceeded)?)
+	}
+
+	/// Checks that descending into a nested array or tuple at `depth` is allowed.
+	fn enter(&self, depth: usize) -> Result<usize, ErrorKind> {
+		if depth >= self.limits.max_depth {
+			return Err(ErrorKind::LimitExceeded);
+		}
+		Ok(depth + 1)
+	}
+}
+
+struct DecodeResult<'a> {
+	token: TokenRef<'a>,
 	new_offset: usize,
+	/// Position just past the words the value takes up, its tail included if it is dynamic.
+	end: usize,
 }
 
-struct BytesTaken {
-	bytes: Vec<u8>,
+/// Values of a param list, tuple or array, laid out as heads followed by tails.
+struct Sequence<'a> {
+	tokens: Vec<TokenRef<'a>>,
+	/// Position just past the heads.
 	new_offset: usize,
+	/// Position just past the last tail.
+	end: usize,
 }
 
-fn as_u32(slice: &Word) -> Result<u32, Error> {
+struct BytesTaken<'a> {
+	bytes: &'a [u8],
+	new_offset: usize,
+}
+
+fn as_u32(slice: &Word) -> Result<u32, ErrorKind> {
 	if !slice[..28].iter().all(|x| *x == 0) {
-		return Err(Error::InvalidData);
+		return Err(ErrorKind::LengthOverflow);
 	}
 
 	let result =
@@ -34,194 +119,462 @@ fn as_u32(slice: &Word) -> Result<u32, Error> {
 	Ok(result)
 }
 
-fn as_bool(slice: &Word) -> Result<bool, Error> {
+fn as_bool(slice: &Word) -> Result<bool, ErrorKind> {
 	if !slice[..31].iter().all(|x| *x == 0) {
-		return Err(Error::InvalidData);
+		return Err(ErrorKind::InvalidBool);
 	}
 
 	Ok(slice[31] == 1)
 }
 
+/// Checks that `slice` holds an `Int(bits)` value sign-extended to 256 bits.
+fn check_int_width(slice: &Word, bits: usize) -> Result<(), ErrorKind> {
+	match fits_int((*slice).into(), bits) {
+		true => Ok(()),
+		false => Err(ErrorKind::DirtyPadding),
+	}
+}
+
+/// Checks that `slice` holds a `Uint(bits)` value zero-extended to 256 bits.
+fn check_uint_width(slice: &Word, bits: usize) -> Result<(), ErrorKind> {
+	match fits_uint((*slice).into(), bits) {
+		true => Ok(()),
+		false => Err(ErrorKind::DirtyPadding),
+	}
+}
+
 /// Decodes ABI compliant vector of bytes into vector of tokens described by types param.
+///
+/// Integers are not checked against the bit width of their type, only the width
+/// itself is, see `ParamKind::uint`. Use `decode_strict` to also reject values
+/// that do not fit. Uses the default `DecodeLimits`.
 pub fn decode(types: &[ParamKind], data: &[u8]) -> Result<Vec<Token>, Error> {
-	let is_empty_bytes_valid_encoding = types.iter().all(|t| t.is_empty_bytes_valid_encoding());
-	if !is_empty_bytes_valid_encoding && data.is_empty() {
-		return Err(Error::InvalidName);
+	decode_with_limits(types, data, &DecodeLimits::default())
+}
+
+/// Decodes ABI compliant vector of bytes into vector of tokens described by types param,
+/// failing with `ErrorKind::LimitExceeded` as soon as decoding would go beyond `limits`.
+pub fn decode_with_limits(types: &[ParamKind], data: &[u8], limits: &DecodeLimits) -> Result<Vec<Token>, Error> {
+	decode_shapes(types.iter(), data, limits, false)
+}
+
+/// Decodes ABI compliant bytes without copying them: bytes and strings in the
+/// returned tokens borrow from `data`.
+///
+/// Uses the default `DecodeLimits`.
+pub fn decode_borrowed<'a>(types: &[ParamKind], data: &'a [u8]) -> Result<Vec<TokenRef<'a>>, Error> {
+	decode_borrowed_with_limits(types, data, &DecodeLimits::default())
+}
+
+/// Same as `decode_borrowed`, but honouring the given `limits`.
+pub fn decode_borrowed_with_limits<'a>(
+	types: &[ParamKind],
+	data: &'a [u8],
+	limits: &DecodeLimits,
+) -> Result<Vec<TokenRef<'a>>, Error> {
+	decode_in_context(types.iter(), data, &mut DecodeContext::new(limits, false))
+}
+
+fn into_owned(tokens: Vec<TokenRef>) -> Vec<Token> {
+	tokens.into_iter().map(Token::from).collect()
+}
+
+/// Decodes ABI compliant vector of bytes, rejecting any encoding other than the
+/// canonical one `encode` would produce for the decoded tokens.
+///
+/// On top of the checks done by `decode`, this fails on dirty high-order bytes in
+/// addresses, integers wider than their declared bit width, booleans other than
+/// 0 or 1, non-zero padding after fixed bytes, bytes and strings, and offsets
+/// that do not point exactly where the canonical encoding places the data
+/// (misaligned, overlapping, backwards or leaving trailing bytes). This mirrors
+/// what Solidity's `abi.decode` reverts on, so a message only has one accepted
+/// encoding.
+///
+/// Uses the default `DecodeLimits`.
+pub fn decode_strict(types: &[ParamKind], data: &[u8]) -> Result<Vec<Token>, Error> {
+	decode_strict_with_limits(types, data, &DecodeLimits::default())
+}
+
+/// Same as `decode_strict`, but honouring the given `limits`.
+pub fn decode_strict_with_limits(types: &[ParamKind], data: &[u8], limits: &DecodeLimits) -> Result<Vec<Token>, Error> {
+	decode_shapes(types.iter(), data, limits, true)
+}
+
+/// Decodes params of any `TypeShape`, like `decode_with_limits`, or like
+/// `decode_strict_with_limits` if `strict` is set.
+pub(crate) fn decode_shapes<'k, K: TypeShape + 'k>(
+	types: impl Iterator<Item = &'k K> + Clone,
+	data: &[u8],
+	limits: &DecodeLimits,
+	strict: bool,
+) -> Result<Vec<Token>, Error> {
+	decode_in_context(types, data, &mut DecodeContext::new(limits, strict)).map(into_owned)
+}
+
+fn decode_in_context<'a, 'k, K: TypeShape + 'k>(
+	types: impl Iterator<Item = &'k K> + Clone,
+	data: &'a [u8],
+	ctx: &mut DecodeContext,
+) -> Result<Vec<TokenRef<'a>>, Error> {
+	if !types.clone().all(is_empty_bytes_valid_encoding) && data.is_empty() {
+		return Err(ErrorKind::EmptyData.into());
 	}
+	ctx.charge(data.len())?;
+	ctx.charge_tokens(types.clone().count())?;
 	let slices = slice_data(data)?;
-	let mut tokens = Vec::with_capacity(types.len());
-	let mut offset = 0;
-	for param in types {
-		let res = decode_param(param, &slices, offset)?;
-		offset = res.new_offset;
+	ctx.words = slices.len();
+	let params = types.enumerate().map(|(i, param)| (PathSegment::Input(i), param));
+	let sequence = decode_sequence(params, slices, 0, ctx, 0)?;
+	// the canonical encoding ends with the last tail
+	if ctx.strict && sequence.end != slices.len() {
+		return Err(ctx.error(ErrorKind::NonCanonical, slices, sequence.end));
+	}
+	Ok(sequence.tokens)
+}
+
+/// Decodes values whose heads follow each other from `offset` on, each one
+/// located by its path segment in errors.
+///
+/// In strict mode, the tail of every dynamic value must start where the
+/// canonical encoding places it: right after the heads for the first one, and
+/// right after the previous tail for the others. This rejects overlapping,
+/// backward and out of order offsets, as well as gaps between tails.
+fn decode_sequence<'a, 'k, K: TypeShape + 'k>(
+	params: impl Iterator<Item = (PathSegment, &'k K)> + Clone,
+	slices: Words<'a>,
+	offset: usize,
+	ctx: &mut DecodeContext,
+	depth: usize,
+) -> Result<Sequence<'a>, Error> {
+	let heads = params.clone().fold(offset, |acc, (_, param)| acc.saturating_add(head_words(param)));
+	// The length of an array is untrusted, so never reserve more than the words could hold.
+	let mut tokens = Vec::with_capacity(params.size_hint().0.min(slices.len()));
+	let (mut new_offset, mut end) = (offset, heads);
+	for (segment, param) in params {
+		let dynamic = is_dynamic(param);
+		if ctx.strict && dynamic && read_offset(slices, new_offset, ctx)? != end {
+			return Err(ctx.error(ErrorKind::NonCanonical, slices, new_offset).within(segment));
+		}
+		let res = decode_param(param, slices, new_offset, ctx, depth).map_err(|e| e.within(segment))?;
+		new_offset = res.new_offset;
+		if dynamic {
+			end = res.end;
+		}
 		tokens.push(res.token);
 	}
-	Ok(tokens)
+	Ok(Sequence { tokens, new_offset, end })
 }
 
-fn peek(slices: &[Word], position: usize) -> Result<&Word, Error> {
-	slices.get(position).ok_or_else(|| Error::InvalidData)
+fn peek<'s>(slices: Words<'s>, position: usize, ctx: &DecodeContext) -> Result<&'s Word, Error> {
+	slices.get(position).ok_or_else(|| ctx.error(ErrorKind::OutOfBounds, slices, position))
 }
 
-fn take_bytes(slices: &[Word], position: usize, len: usize) -> Result<BytesTaken, Error> {
-	let slices_len = (len + 31) / 32;
+/// Returns the words starting at `position`, failing if `position` lies past the end of `slices`.
+fn take_tail<'s>(slices: Words<'s>, position: usize, ctx: &DecodeContext) -> Result<Words<'s>, Error> {
+	slices.tail(position).ok_or_else(|| ctx.error(ErrorKind::OutOfBounds, slices, position))
+}
 
-	let mut bytes_slices = Vec::with_capacity(slices_len);
-	for i in 0..slices_len {
-		let slice = peek(slices, position + i)?;
-		bytes_slices.push(slice);
+/// Reads the byte offset stored at `offset` and converts it to a word position.
+fn read_offset(slices: Words, offset: usize, ctx: &DecodeContext) -> Result<usize, Error> {
+	let byte_offset = as_u32(peek(slices, offset, ctx)?).map_err(|kind| ctx.error(kind, slices, offset))?;
+	if ctx.strict && byte_offset % 32 != 0 {
+		return Err(ctx.error(ErrorKind::MisalignedOffset, slices, offset));
 	}
+	Ok(byte_offset as usize / 32)
+}
 
-	let bytes = bytes_slices.into_iter().flat_map(|slice| slice.to_vec()).take(len).collect();
+/// Reads the length stored at `position`.
+fn read_len(slices: Words, position: usize, ctx: &DecodeContext) -> Result<usize, Error> {
+	let len = as_u32(peek(slices, position, ctx)?).map_err(|kind| ctx.error(kind, slices, position))?;
+	Ok(len as usize)
+}
+
+/// Follows the offset word found at `offset` and returns the words it points to.
+fn tail_at_offset<'s>(slices: Words<'s>, offset: usize, ctx: &DecodeContext) -> Result<Words<'s>, Error> {
+	take_tail(slices, read_offset(slices, offset, ctx)?, ctx)
+}
+
+fn take_bytes<'a>(
+	slices: Words<'a>,
+	position: usize,
+	len: usize,
+	ctx: &mut DecodeContext,
+) -> Result<BytesTaken<'a>, Error> {
+	ctx.charge(len).map_err(|kind| ctx.error(kind, slices, position))?;
+	let slices_len = (len + 31) / 32;
 
-	let taken = BytesTaken { bytes, new_offset: position + slices_len };
+	// Make sure all the words are there before allocating anything for them.
+	let end = position.checked_add(slices_len).ok_or_else(|| ctx.error(ErrorKind::LengthOverflow, slices, position))?;
+	let bytes = slices.bytes(position, end).ok_or_else(|| ctx.error(ErrorKind::OutOfBounds, slices, position))?;
+	let (bytes, padding) = bytes.split_at(len);
+
+	if ctx.strict && padding.iter().any(|b| *b != 0) {
+		return Err(ctx.error(ErrorKind::DirtyPadding, slices, end - 1));
+	}
+
+	let taken = BytesTaken { bytes, new_offset: end };
 
 	Ok(taken)
 }
 
-fn decode_param(param: &ParamKind, slices: &[Word], offset: usize) -> Result<DecodeResult, Error> {
-	match *param {
-		ParamKind::Address => {
-			let slice = peek(slices, offset)?;
+fn decode_param<'a, K: TypeShape>(
+	param: &K,
+	slices: Words<'a>,
+	offset: usize,
+	ctx: &mut DecodeContext,
+	depth: usize,
+) -> Result<DecodeResult<'a>, Error> {
+	match param.shape() {
+		Shape::Address => {
+			let slice = peek(slices, offset, ctx)?;
+			if ctx.strict && slice[..12].iter().any(|b| *b != 0) {
+				return Err(ctx.error(ErrorKind::DirtyPadding, slices, offset));
+			}
 			let mut address = [0u8; 20];
 			address.copy_from_slice(&slice[12..]);
 
-			let result = DecodeResult { token: Token::Address(address.into()), new_offset: offset + 1 };
+			let result =
+				DecodeResult { token: TokenRef::Address(address.into()), new_offset: offset + 1, end: offset + 1 };
 
 			Ok(result)
 		}
-		ParamKind::Int(_) => {
-			let slice = peek(slices, offset)?;
+		Shape::Int(bits) => {
+			if !is_valid_int_width(bits) {
+				return Err(ctx.error(ErrorKind::InvalidType, slices, offset));
+			}
+			let slice = peek(slices, offset, ctx)?;
+			if ctx.strict {
+				check_int_width(slice, bits).map_err(|kind| ctx.error(kind, slices, offset))?;
+			}
 
-			let result = DecodeResult { token: Token::Int(slice.clone().into()), new_offset: offset + 1 };
+			let result =
+				DecodeResult { token: TokenRef::Int((*slice).into()), new_offset: offset + 1, end: offset + 1 };
 
 			Ok(result)
 		}
-		ParamKind::Uint(_) => {
-			let slice = peek(slices, offset)?;
+		Shape::Uint(bits) => {
+			if !is_valid_int_width(bits) {
+				return Err(ctx.error(ErrorKind::InvalidType, slices, offset));
+			}
+			let slice = peek(slices, offset, ctx)?;
+			if ctx.strict {
+				check_uint_width(slice, bits).map_err(|kind| ctx.error(kind, slices, offset))?;
+			}
 
-			let result = DecodeResult { token: Token::Uint(slice.clone().into()), new_offset: offset + 1 };
+			let result =
+				DecodeResult { token: TokenRef::Uint((*slice).into()), new_offset: offset + 1, end: offset + 1 };
 
 			Ok(result)
 		}
-		ParamKind::Bool => {
-			let slice = peek(slices, offset)?;
+		Shape::Bool => {
+			let slice = peek(slices, offset, ctx)?;
+			if ctx.strict && slice[31] > 1 {
+				return Err(ctx.error(ErrorKind::InvalidBool, slices, offset));
+			}
 
-			let b = as_bool(slice)?;
+			let b = as_bool(slice).map_err(|kind| ctx.error(kind, slices, offset))?;
 
-			let result = DecodeResult { token: Token::Bool(b), new_offset: offset + 1 };
+			let result = DecodeResult { token: TokenRef::Bool(b), new_offset: offset + 1, end: offset + 1 };
 			Ok(result)
 		}
-		ParamKind::FixedBytes(len) => {
+		Shape::FixedBytes(len) => {
 			// FixedBytes is anything from bytes1 to bytes32. These values
 			// are padded with trailing zeros to fill 32 bytes.
-			let taken = take_bytes(slices, offset, len)?;
-			let result = DecodeResult { token: Token::FixedBytes(taken.bytes), new_offset: taken.new_offset };
+			let taken = take_bytes(slices, offset, len, ctx)?;
+			let result = DecodeResult {
+				token: TokenRef::FixedBytes(taken.bytes),
+				new_offset: taken.new_offset,
+				end: taken.new_offset,
+			};
 			Ok(result)
 		}
-		ParamKind::Bytes => {
-			let offset_slice = peek(slices, offset)?;
-			let len_offset = (as_u32(offset_slice)? / 32) as usize;
-
-			let len_slice = peek(slices, len_offset)?;
-			let len = as_u32(len_slice)? as usize;
+		Shape::Bytes => {
+			let len_offset = read_offset(slices, offset, ctx)?;
+			let len = read_len(slices, len_offset, ctx)?;
 
-			let taken = take_bytes(slices, len_offset + 1, len)?;
+			let taken = take_bytes(slices, len_offset + 1, len, ctx)?;
 
-			let result = DecodeResult { token: Token::Bytes(taken.bytes), new_offset: offset + 1 };
+			let result =
+				DecodeResult { token: TokenRef::Bytes(taken.bytes), new_offset: offset + 1, end: taken.new_offset };
 			Ok(result)
 		}
-		ParamKind::String => {
-			let offset_slice = peek(slices, offset)?;
-			let len_offset = (as_u32(offset_slice)? / 32) as usize;
+		Shape::String => {
+			let len_offset = read_offset(slices, offset, ctx)?;
+			let len = read_len(slices, len_offset, ctx)?;
 
-			let len_slice = peek(slices, len_offset)?;
-			let len = as_u32(len_slice)? as usize;
+			let taken = take_bytes(slices, len_offset + 1, len, ctx)?;
 
-			let taken = take_bytes(slices, len_offset + 1, len)?;
-
-			let result = DecodeResult { token: Token::String(taken.bytes), new_offset: offset + 1 };
+			let result =
+				DecodeResult { token: TokenRef::String(taken.bytes), new_offset: offset + 1, end: taken.new_offset };
 			Ok(result)
 		}
-		ParamKind::Array(ref t) => {
-			let offset_slice = peek(slices, offset)?;
-			let len_offset = (as_u32(offset_slice)? / 32) as usize;
-			let len_slice = peek(slices, len_offset)?;
-			let len = as_u32(len_slice)? as usize;
-
-			let tail = &slices[len_offset + 1..];
-			let mut tokens = Vec::with_capacity(len);
-			let mut new_offset = 0;
-
-			for _ in 0..len {
-				let res = decode_param(t, &tail, new_offset)?;
-				new_offset = res.new_offset;
-				tokens.push(res.token);
+		Shape::Array(t) => {
+			let len_offset = read_offset(slices, offset, ctx)?;
+			let len = read_len(slices, len_offset, ctx)?;
+			if len > ctx.limits.max_array_length {
+				return Err(ctx.error(ErrorKind::LimitExceeded, slices, len_offset));
 			}
+			let depth = ctx.enter(depth).map_err(|kind| ctx.error(kind, slices, offset))?;
+			ctx.charge_tokens(len).map_err(|kind| ctx.error(kind, slices, len_offset))?;
 
-			let result = DecodeResult { token: Token::Array(tokens), new_offset: offset + 1 };
+			let tail = take_tail(slices, len_offset + 1, ctx)?;
+			let elements = (0..len).map(|i| (PathSegment::Element(i), t));
+			let sequence = decode_sequence(elements, tail, 0, ctx, depth)?;
+
+			let result = DecodeResult {
+				token: TokenRef::Array(sequence.tokens),
+				new_offset: offset + 1,
+				end: len_offset + 1 + sequence.end,
+			};
 
 			Ok(result)
 		}
-		ParamKind::FixedArray(ref t, len) => {
-			let mut tokens = Vec::with_capacity(len);
-			let is_dynamic = param.is_dynamic();
-
-			let (tail, mut new_offset) = if is_dynamic {
-				(&slices[(as_u32(peek(slices, offset)?)? as usize / 32)..], 0)
-			} else {
-				(slices, offset)
-			};
+		Shape::FixedArray(t, len) => {
+			let depth = ctx.enter(depth).map_err(|kind| ctx.error(kind, slices, offset))?;
+			ctx.charge_tokens(len).map_err(|kind| ctx.error(kind, slices, offset))?;
+			let elements = (0..len).map(|i| (PathSegment::Element(i), t));
 
-			for _ in 0..len {
-				let res = decode_param(t, &tail, new_offset)?;
-				new_offset = res.new_offset;
-				tokens.push(res.token);
-			}
+			decode_nested(param, elements, slices, offset, ctx, depth, TokenRef::FixedArray)
+		}
+		Shape::Tuple(t) => {
+			let depth = ctx.enter(depth).map_err(|kind| ctx.error(kind, slices, offset))?;
+			ctx.charge_tokens(t.len()).map_err(|kind| ctx.error(kind, slices, offset))?;
+			let components = t.iter().enumerate().map(|(i, c)| (PathSegment::Component(i), c.as_ref()));
 
-			let result = DecodeResult {
-				token: Token::FixedArray(tokens),
-				new_offset: if is_dynamic { offset + 1 } else { new_offset },
-			};
+			decode_nested(param, components, slices, offset, ctx, depth, TokenRef::Tuple)
+		}
+	}
+}
 
-			Ok(result)
+/// Decodes a fixed array or a tuple from its `elements`.
+fn decode_nested<'a, 'k, K: TypeShape + 'k>(
+	param: &K,
+	elements: impl Iterator<Item = (PathSegment, &'k K)> + Clone,
+	slices: Words<'a>,
+	offset: usize,
+	ctx: &mut DecodeContext,
+	depth: usize,
+	token: fn(Vec<TokenRef<'a>>) -> TokenRef<'a>,
+) -> Result<DecodeResult<'a>, Error> {
+	// The head of a dynamic value is an offset to its data, while the data of a
+	// static value begins right away and its elements follow each other in the head.
+	match is_dynamic(param) {
+		true => {
+			let position = read_offset(slices, offset, ctx)?;
+			let sequence = decode_sequence(elements, take_tail(slices, position, ctx)?, 0, ctx, depth)?;
+			Ok(DecodeResult { token: token(sequence.tokens), new_offset: offset + 1, end: position + sequence.end })
 		}
-		ParamKind::Tuple(ref t) => {
-			let is_dynamic = param.is_dynamic();
-
-			// The first element in a dynamic Tuple is an offset to the Tuple's data
-			// For a static Tuple the data begins right away
-			let (tail, mut new_offset) = if is_dynamic {
-				(&slices[(as_u32(peek(slices, offset)?)? as usize / 32)..], 0)
-			} else {
-				(slices, offset)
-			};
+		false => {
+			let sequence = decode_sequence(elements, slices, offset, ctx, depth)?;
+			Ok(DecodeResult { token: token(sequence.tokens), new_offset: sequence.new_offset, end: sequence.end })
+		}
+	}
+}
 
-			let len = t.len();
-			let mut tokens = Vec::with_capacity(len);
-			for i in 0..len {
-				let res = decode_param(&t[i], &tail, new_offset)?;
-				new_offset = res.new_offset;
-				tokens.push(res.token);
-			}
+/// Number of words `kind` takes in the head of its enclosing tuple or array.
+pub(crate) fn head_words<K: TypeShape>(kind: &K) -> usize {
+	match kind.shape() {
+		_ if is_dynamic(kind) => 1,
+		// like `encode`, empty fixed bytes take no space at all
+		Shape::FixedBytes(0) => 0,
+		Shape::FixedArray(t, len) => head_words(t).saturating_mul(len),
+		Shape::Tuple(t) => t.iter().fold(0, |acc, t| acc.saturating_add(head_words(t.as_ref()))),
+		_ => 1,
+	}
+}
 
-			// The returned new_offset depends on whether the Tuple is dynamic
-			// dynamic Tuple -> follows the prefixed Tuple data offset element
-			// static Tuple  -> follows the last data element
-			let result = DecodeResult {
-				token: Token::Tuple(tokens),
-				new_offset: if is_dynamic { offset + 1 } else { new_offset },
-			};
+/// View over encoded data that only decodes the values it is asked for.
+///
+/// Navigating to a value follows the same head and tail offsets `decode` does,
+/// without decoding or allocating any of the values around it.
+#[derive(Debug, Clone)]
+pub struct LazyDecoder<'a> {
+	types: &'a [ParamKind],
+	slices: Words<'a>,
+	limits: DecodeLimits,
+}
 
-			Ok(result)
+impl<'a> LazyDecoder<'a> {
+	/// Creates a view over `data` encoded according to `types`, using the default `DecodeLimits`.
+	pub fn new(types: &'a [ParamKind], data: &'a [u8]) -> Result<Self, Error> {
+		Self::with_limits(types, data, DecodeLimits::default())
+	}
+
+	/// Creates a view over `data` encoded according to `types`, honouring `limits` on every access.
+	pub fn with_limits(types: &'a [ParamKind], data: &'a [u8], limits: DecodeLimits) -> Result<Self, Error> {
+		Ok(LazyDecoder { types, slices: slice_data(data)?, limits })
+	}
+
+	/// Decodes the value at `path`, like `[Input(2), Element(5), Component(1)]`.
+	///
+	/// Fails with `ErrorKind::InvalidPath` if the path does not start with an
+	/// input or does not match the shape of the types, and with
+	/// `ErrorKind::OutOfBounds` if it indexes past the length of a dynamic array.
+	pub fn get(&self, path: &[PathSegment]) -> Result<TokenRef<'a>, Error> {
+		let mut ctx = DecodeContext::new(&self.limits, false);
+		ctx.words = self.slices.len();
+
+		match path.split_first() {
+			Some((&PathSegment::Input(i), rest)) if i < self.types.len() => {
+				let offset = self.types[..i].iter().fold(0usize, |acc, t| acc.saturating_add(head_words(t)));
+				descend(&self.types[i], self.slices, offset, rest, &mut ctx, 0)
+					.map_err(|e| e.within(PathSegment::Input(i)))
+			}
+			_ => Err(ErrorKind::InvalidPath.into()),
 		}
 	}
 }
 
+/// Follows `path` from the value of type `param` whose head is at `offset`, then decodes the value reached.
+fn descend<'a>(
+	param: &ParamKind,
+	slices: Words<'a>,
+	offset: usize,
+	path: &[PathSegment],
+	ctx: &mut DecodeContext,
+	depth: usize,
+) -> Result<TokenRef<'a>, Error> {
+	let (segment, rest) = match path.split_first() {
+		Some((segment, rest)) => (*segment, rest),
+		None => return decode_param(param, slices, offset, ctx, depth).map(|res| res.token),
+	};
+
+	let depth = ctx.enter(depth).map_err(|kind| ctx.error(kind, slices, offset))?;
+	let (kind, tail, offset) = match (param, segment) {
+		(ParamKind::Array(t), PathSegment::Element(i)) => {
+			let len_offset = read_offset(slices, offset, ctx)?;
+			if i >= read_len(slices, len_offset, ctx)? {
+				return Err(ctx.error(ErrorKind::OutOfBounds, slices, len_offset).within(segment));
+			}
+			(&**t, take_tail(slices, len_offset + 1, ctx)?, i.saturating_mul(head_words(&**t)))
+		}
+		(ParamKind::FixedArray(t, len), PathSegment::Element(i)) if i < *len => {
+			let (tail, base) = match param.is_dynamic() {
+				true => (tail_at_offset(slices, offset, ctx)?, 0),
+				false => (slices, offset),
+			};
+			(&**t, tail, base.saturating_add(i.saturating_mul(head_words(&**t))))
+		}
+		(ParamKind::Tuple(t), PathSegment::Component(i)) if i < t.len() => {
+			let (tail, base) = match param.is_dynamic() {
+				true => (tail_at_offset(slices, offset, ctx)?, 0),
+				false => (slices, offset),
+			};
+			(&*t[i], tail, t[..i].iter().fold(base, |acc, t| acc.saturating_add(head_words(&**t))))
+		}
+		_ => return Err(ErrorKind::InvalidPath.into()),
+	};
+
+	descend(kind, tail, offset, rest, ctx, depth).map_err(|e| e.within(segment))
+}
+
 #[cfg(test)]
 mod tests {
 
-	use crate::{decode, ParamKind, Token};
+	use crate::{
+		decode, decode_borrowed, decode_strict, decode_with_limits, encode, DecodeLimits, ErrorKind, LazyDecoder,
+		ParamKind, PathSegment, Token, TokenRef,
+	};
 	use hex_literal::hex;
 
 	#[test]
@@ -522,4 +875,367 @@ mod tests {
 			]
 		);
 	}
+
+	#[test]
+	fn decode_array_longer_than_max_array_length() {
+		let encoded = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000001
+		"
+		);
+		let types = [ParamKind::Array(Box::new(ParamKind::Bool))];
+		let limits = DecodeLimits { max_array_length: 2, ..Default::default() };
+
+		assert_eq!(decode_with_limits(&types, &encoded, &limits).unwrap_err().kind, ErrorKind::LimitExceeded);
+		assert_eq!(decode(&types, &encoded).unwrap().len(), 1);
+	}
+
+	#[test]
+	fn decode_array_of_zero_sized_elements_is_bounded() {
+		// every element occupies no words at all, so only the limits stop this loop
+		let encoded = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000020
+			00000000000000000000000000000000000000000000000000000000ffffffff
+		"
+		);
+		let types = [ParamKind::Array(Box::new(ParamKind::FixedArray(Box::new(ParamKind::Bool), 0)))];
+
+		assert_eq!(decode(&types, &encoded).unwrap_err().kind, ErrorKind::LimitExceeded);
+	}
+
+	#[test]
+	fn decode_bytes_larger_than_max_allocation() {
+		let encoded = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000040
+			1111111111111111111111111111111111111111111111111111111111111111
+			2222222222222222222222222222222222222222222222222222222222222222
+		"
+		);
+		// the input alone takes up 128 bytes, the decoded bytes another 64
+		let limits = DecodeLimits { max_allocation: 128 + 64, ..Default::default() };
+		assert_eq!(
+			decode_with_limits(&[ParamKind::Bytes], &encoded, &limits).unwrap_err().kind,
+			ErrorKind::LimitExceeded
+		);
+	}
+
+	#[test]
+	fn decode_nested_deeper_than_max_depth() {
+		let encoded = hex!("0000000000000000000000000000000000000000000000000000000000000001");
+		let types = [ParamKind::Tuple(vec![Box::new(ParamKind::Tuple(vec![Box::new(ParamKind::Bool)]))])];
+		let limits = DecodeLimits { max_depth: 1, ..Default::default() };
+
+		assert_eq!(decode_with_limits(&types, &encoded, &limits).unwrap_err().kind, ErrorKind::LimitExceeded);
+		let limits = DecodeLimits { max_depth: 2, ..Default::default() };
+		assert!(decode_with_limits(&types, &encoded, &limits).is_ok());
+	}
+
+	#[test]
+	fn decode_strict_rejects_dirty_static_values() {
+		let dirty_address = hex!("0100000000000000000000001111111111111111111111111111111111111111");
+		assert!(decode(&[ParamKind::Address], &dirty_address).is_ok());
+		assert!(decode_strict(&[ParamKind::Address], &dirty_address).is_err());
+
+		let bool_two = hex!("0000000000000000000000000000000000000000000000000000000000000002");
+		assert!(decode(&[ParamKind::Bool], &bool_two).is_ok());
+		assert!(decode_strict(&[ParamKind::Bool], &bool_two).is_err());
+
+		let dirty_bytes4 = hex!("1234567800000000000000000000000000000000000000000000000000000001");
+		assert!(decode(&[ParamKind::FixedBytes(4)], &dirty_bytes4).is_ok());
+		assert!(decode_strict(&[ParamKind::FixedBytes(4)], &dirty_bytes4).is_err());
+	}
+
+	#[test]
+	fn decode_strict_checks_integer_width() {
+		let uint8_max = hex!("00000000000000000000000000000000000000000000000000000000000000ff");
+		let uint16 = hex!("0000000000000000000000000000000000000000000000000000000000000100");
+		assert!(decode_strict(&[ParamKind::Uint(8)], &uint8_max).is_ok());
+		assert!(decode_strict(&[ParamKind::Uint(8)], &uint16).is_err());
+		assert!(decode_strict(&[ParamKind::Uint(16)], &uint16).is_ok());
+
+		let minus_one = hex!("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+		let int8_min = hex!("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff80");
+		let not_sign_extended = hex!("0000000000000000000000000000000000000000000000000000000000000080");
+		assert!(decode_strict(&[ParamKind::Int(8)], &minus_one).is_ok());
+		assert!(decode_strict(&[ParamKind::Int(8)], &int8_min).is_ok());
+		assert!(decode_strict(&[ParamKind::Int(8)], &not_sign_extended).is_err());
+		assert!(decode_strict(&[ParamKind::Int(16)], &not_sign_extended).is_ok());
+
+		// the lenient decoder keeps out of range values, which then fail to type check
+		let tokens = decode(&[ParamKind::Uint(8)], &uint16).unwrap();
+		assert!(!Token::types_check(&tokens, &[ParamKind::Uint(8)]));
+	}
+
+	#[test]
+	fn decode_rejects_invalid_int_width() {
+		let word = [0u8; 32];
+		for kind in [ParamKind::Uint(7), ParamKind::Int(0), ParamKind::Uint(264)] {
+			assert_eq!(decode(&[kind], &word).unwrap_err().kind, ErrorKind::InvalidType);
+		}
+	}
+
+	#[test]
+	fn decode_strict_rejects_dirty_bytes_padding() {
+		let encoded = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			1234000000000000000000000000000000000000000000000000000000000001
+		"
+		);
+		assert_eq!(decode(&[ParamKind::String], &encoded).unwrap(), vec![Token::String(vec![0x12, 0x34])]);
+		assert!(decode_strict(&[ParamKind::String], &encoded).is_err());
+		assert!(decode_strict(&[ParamKind::Bytes], &encoded).is_err());
+	}
+
+	#[test]
+	fn decode_strict_rejects_non_canonical_offsets() {
+		let misaligned = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000021
+			0000000000000000000000000000000000000000000000000000000000000001
+			1200000000000000000000000000000000000000000000000000000000000000
+		"
+		);
+		assert!(decode(&[ParamKind::Bytes], &misaligned).is_ok());
+		assert!(decode_strict(&[ParamKind::Bytes], &misaligned).is_err());
+
+		// both params share the same tail
+		let overlapping = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000001
+			1200000000000000000000000000000000000000000000000000000000000000
+		"
+		);
+		assert!(decode(&[ParamKind::Bytes, ParamKind::Bytes], &overlapping).is_ok());
+		assert!(decode_strict(&[ParamKind::Bytes, ParamKind::Bytes], &overlapping).is_err());
+
+		// the offset points back into the head
+		let backwards = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000000
+		"
+		);
+		assert!(decode(&[ParamKind::Bytes], &backwards).is_ok());
+		assert!(decode_strict(&[ParamKind::Bytes], &backwards).is_err());
+
+		let trailing = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000000
+		"
+		);
+		assert!(decode(&[ParamKind::Bool], &trailing).is_ok());
+		assert!(decode_strict(&[ParamKind::Bool], &trailing).is_err());
+	}
+
+	#[test]
+	fn decode_strict_tail_layout() {
+		let bytes = [ParamKind::Bytes, ParamKind::Bytes];
+		let tokens = vec![Token::Bytes(vec![0x12]), Token::Bytes(vec![0x34])];
+		assert_eq!(decode_strict(&bytes, &encode(&tokens)).unwrap(), tokens);
+
+		// the tails are swapped
+		let swapped = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000001
+			3400000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000001
+			1200000000000000000000000000000000000000000000000000000000000000
+		"
+		);
+		assert_eq!(decode(&bytes, &swapped).unwrap(), tokens);
+		let error = decode_strict(&bytes, &swapped).unwrap_err();
+		assert_eq!((error.kind, error.offset), (ErrorKind::NonCanonical, Some(0)));
+		assert_eq!(error.path.segments(), &[PathSegment::Input(0)]);
+
+		// a word is skipped between the head and the tail
+		let gap = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000001
+			1200000000000000000000000000000000000000000000000000000000000000
+		"
+		);
+		assert!(decode(&[ParamKind::Bytes], &gap).is_ok());
+		assert_eq!(decode_strict(&[ParamKind::Bytes], &gap).unwrap_err().kind, ErrorKind::NonCanonical);
+
+		// the elements of an array share the same tail
+		let array = [ParamKind::Array(Box::new(ParamKind::Bytes))];
+		let shared = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000001
+			1200000000000000000000000000000000000000000000000000000000000000
+		"
+		);
+		assert!(decode(&array, &shared).is_ok());
+		let error = decode_strict(&array, &shared).unwrap_err();
+		assert_eq!((error.kind, error.offset), (ErrorKind::NonCanonical, Some(3 * 32)));
+		assert_eq!(error.path.segments(), &[PathSegment::Input(0), PathSegment::Element(1)]);
+	}
+
+	#[test]
+	fn decode_error_reports_path_and_offset() {
+		let encoded = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000005
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000006
+			0000000000000000000000000000000000000000000000000000000000000100
+		"
+		);
+		let element = ParamKind::Tuple(vec![Box::new(ParamKind::Uint(256)), Box::new(ParamKind::Bool)]);
+		let types = [ParamKind::Bool, ParamKind::Array(Box::new(element))];
+
+		let error = decode(&types, &encoded).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::InvalidBool);
+		assert_eq!(error.path.segments(), &[PathSegment::Input(1), PathSegment::Element(1), PathSegment::Component(1)]);
+		assert_eq!(error.offset, Some(6 * 32));
+	}
+
+	#[test]
+	fn decode_error_kinds() {
+		let out_of_bounds = hex!("0000000000000000000000000000000000000000000000000000000000000040");
+		let error = decode(&[ParamKind::Bytes], &out_of_bounds).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::OutOfBounds);
+		assert_eq!(error.offset, Some(64));
+
+		let overflow = hex!("0000000000000000000000000000000000000000000000000000000100000000");
+		let error = decode(&[ParamKind::String], &overflow).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::LengthOverflow);
+		assert_eq!(error.offset, Some(0));
+
+		assert_eq!(decode(&[ParamKind::Bool], &[]).unwrap_err().kind, ErrorKind::EmptyData);
+		assert_eq!(decode(&[ParamKind::Bool], &[0u8; 31]).unwrap_err().kind, ErrorKind::UnalignedData);
+
+		let dirty_address = hex!("0100000000000000000000001111111111111111111111111111111111111111");
+		assert_eq!(decode_strict(&[ParamKind::Address], &dirty_address).unwrap_err().kind, ErrorKind::DirtyPadding);
+
+		let trailing = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000000
+		"
+		);
+		let error = decode_strict(&[ParamKind::Bool], &trailing).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::NonCanonical);
+		assert_eq!(error.offset, Some(32));
+	}
+
+	#[test]
+	fn decode_borrowed_points_into_input() {
+		let types =
+			[ParamKind::Bytes, ParamKind::Tuple(vec![Box::new(ParamKind::String), Box::new(ParamKind::FixedBytes(2))])];
+		let encoded = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000003
+			6162630000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000040
+			ffee000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000002
+			6869000000000000000000000000000000000000000000000000000000000000
+		"
+		);
+
+		let tokens = decode_borrowed(&types, &encoded).unwrap();
+		assert_eq!(
+			tokens,
+			vec![
+				TokenRef::Bytes(b"abc"),
+				TokenRef::Tuple(vec![TokenRef::String(b"hi"), TokenRef::FixedBytes(&[0xff, 0xee])]),
+			]
+		);
+		match tokens[0] {
+			TokenRef::Bytes(bytes) => assert!(core::ptr::eq(bytes.as_ptr(), &encoded[96])),
+			_ => unreachable!(),
+		}
+
+		let owned: Vec<Token> = tokens.into_iter().map(TokenRef::into_owned).collect();
+		assert_eq!(owned, decode(&types, &encoded).unwrap());
+	}
+
+	#[test]
+	fn lazy_decoder_get() {
+		// (bool, (address, uint256, string)[], uint8[2])
+		let transfer = ParamKind::Tuple(vec![
+			Box::new(ParamKind::Address),
+			Box::new(ParamKind::Uint(256)),
+			Box::new(ParamKind::String),
+		]);
+		let types = [
+			ParamKind::Bool,
+			ParamKind::Array(Box::new(transfer)),
+			ParamKind::FixedArray(Box::new(ParamKind::Uint(8)), 2),
+		];
+		let tokens = [
+			Token::Bool(true),
+			Token::Array(vec![
+				Token::Tuple(vec![
+					Token::Address([0x11u8; 20].into()),
+					Token::Uint(100.into()),
+					Token::String(b"a".to_vec()),
+				]),
+				Token::Tuple(vec![
+					Token::Address([0x22u8; 20].into()),
+					Token::Uint(200.into()),
+					Token::String(b"b".to_vec()),
+				]),
+			]),
+			Token::FixedArray(vec![Token::Uint(7.into()), Token::Uint(8.into())]),
+		];
+		let encoded = crate::encode(&tokens);
+		let view = LazyDecoder::new(&types, &encoded).unwrap();
+
+		use PathSegment::*;
+		assert_eq!(view.get(&[Input(0)]).unwrap(), TokenRef::Bool(true));
+		assert_eq!(view.get(&[Input(1), Element(1), Component(1)]).unwrap(), TokenRef::Uint(200.into()));
+		assert_eq!(view.get(&[Input(1), Element(0), Component(2)]).unwrap(), TokenRef::String(b"a"));
+		assert_eq!(view.get(&[Input(2), Element(1)]).unwrap(), TokenRef::Uint(8.into()));
+		assert_eq!(view.get(&[Input(1)]).unwrap().into_owned(), tokens[1]);
+
+		let error = view.get(&[Input(1), Element(2), Component(1)]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::OutOfBounds);
+		assert_eq!(error.path.segments(), &[Input(1), Element(2)]);
+
+		for path in [&[][..], &[Input(3)], &[Element(0)], &[Input(0), Element(0)], &[Input(2), Element(2)]] {
+			assert_eq!(view.get(path).unwrap_err().kind, ErrorKind::InvalidPath);
+		}
+	}
+
+	#[test]
+	fn lazy_decoder_skips_siblings() {
+		// the first string has a bogus offset, which a full decode rejects
+		let types = [ParamKind::String, ParamKind::Uint(256)];
+		let encoded = hex!(
+			"
+			00000000000000000000000000000000000000000000000000000000ffffffff
+			000000000000000000000000000000000000000000000000000000000000002a
+		"
+		);
+
+		assert!(decode(&types, &encoded).is_err());
+		let view = LazyDecoder::new(&types, &encoded).unwrap();
+		assert_eq!(view.get(&[PathSegment::Input(1)]).unwrap(), TokenRef::Uint(42.into()));
+	}
 }
@@ -8,9 +8,14 @@
 
 //! ABI encoder.
 
-use crate::{util::pad_u32, Token, Word};
-use tiny_keccak::{Hasher, Keccak};
 use crate::std::{vec, Vec};
+use crate::{
+	param::is_valid_int_width,
+	util::{fits_int, fits_uint, pad_u32},
+	Error, ErrorKind, ParamKind, PathSegment, Token, Word,
+};
+use ethabi_decode_syntax::{Shape, TypeShape};
+use tiny_keccak::{Hasher, Keccak};
 
 fn pad_bytes(bytes: &[u8]) -> Vec<Word> {
 	let mut result = vec![pad_u32(bytes.len() as u32)];
@@ -54,7 +59,7 @@ impl Mediate {
 	fn head_len(&self) -> u32 {
 		match *self {
 			Mediate::Raw(ref raw) => 32 * raw.len() as u32,
-			Mediate::RawTuple(ref mediates) => 32 * mediates.len() as u32,
+			Mediate::RawTuple(ref mediates) => mediates.iter().map(|m| m.head_len()).sum(),
 			Mediate::Prefixed(_)
 			| Mediate::PrefixedArray(_)
 			| Mediate::PrefixedArrayWithLength(_)
@@ -129,6 +134,69 @@ pub fn encode(tokens: &[Token]) -> Vec<u8> {
 	encode_head_tail(mediates).iter().flat_map(|word| word.to_vec()).collect()
 }
 
+/// Encodes tokens as values of the given param types.
+///
+/// Unlike `encode`, fails with `ErrorKind::TypeMismatch` if a token does not
+/// match its param type, and with `ErrorKind::ValueOutOfRange` if an integer does
+/// not fit the bit width of its type. `Int` values must be sign-extended to 256
+/// bits, as built by `Token::from_i64` and `Token::from_i128`.
+pub fn encode_params(params: &[ParamKind], tokens: &[Token]) -> Result<Vec<u8>, Error> {
+	encode_shapes(params.iter(), tokens)
+}
+
+/// Same as `encode_params`, for param types of any `TypeShape`.
+pub(crate) fn encode_shapes<'k, K: TypeShape + 'k>(
+	params: impl ExactSizeIterator<Item = &'k K>,
+	tokens: &[Token],
+) -> Result<Vec<u8>, Error> {
+	if params.len() != tokens.len() {
+		return Err(ErrorKind::TypeMismatch.into());
+	}
+
+	Ok(encode(&conform_all(params, tokens, PathSegment::Input)?))
+}
+
+/// Checks `token` against `kind`, returning it in the form `encode` expects for that type.
+pub(crate) fn conform<K: TypeShape>(kind: &K, token: &Token) -> Result<Token, Error> {
+	match (kind.shape(), token) {
+		(Shape::Int(bits), Token::Int(int)) if is_valid_int_width(bits) => match fits_int(*int, bits) {
+			true => Ok(token.clone()),
+			false => Err(ErrorKind::ValueOutOfRange.into()),
+		},
+		(Shape::Uint(bits), Token::Uint(uint)) if is_valid_int_width(bits) => match fits_uint(*uint, bits) {
+			true => Ok(token.clone()),
+			false => Err(ErrorKind::ValueOutOfRange.into()),
+		},
+		(Shape::Array(element), Token::Array(tokens)) => {
+			Ok(Token::Array(conform_all(core::iter::repeat(element), tokens, PathSegment::Element)?))
+		}
+		(Shape::FixedArray(element, len), Token::FixedArray(tokens)) if tokens.len() == len => {
+			Ok(Token::FixedArray(conform_all(core::iter::repeat(element), tokens, PathSegment::Element)?))
+		}
+		(Shape::Tuple(components), Token::Tuple(tokens)) if tokens.len() == components.len() => {
+			Ok(Token::Tuple(conform_all(components.iter().map(AsRef::as_ref), tokens, PathSegment::Component)?))
+		}
+		(Shape::FixedBytes(len), Token::FixedBytes(bytes)) if bytes.len() <= len => Ok(token.clone()),
+		(Shape::Address, Token::Address(_))
+		| (Shape::Bool, Token::Bool(_))
+		| (Shape::Bytes, Token::Bytes(_))
+		| (Shape::String, Token::String(_)) => Ok(token.clone()),
+		_ => Err(ErrorKind::TypeMismatch.into()),
+	}
+}
+
+fn conform_all<'k, K: TypeShape + 'k>(
+	kinds: impl Iterator<Item = &'k K>,
+	tokens: &[Token],
+	segment: fn(usize) -> PathSegment,
+) -> Result<Vec<Token>, Error> {
+	kinds
+		.zip(tokens)
+		.enumerate()
+		.map(|(i, (kind, token))| conform(kind, token).map_err(|e| e.within(segment(i))))
+		.collect()
+}
+
 pub fn encode_function(signature: &str, inputs: &[Token]) -> Vec<u8> {
 	let mut signed: [u8; 4] = [0; 4];
 	let mut sponge = Keccak::v256();
@@ -187,7 +255,7 @@ fn encode_token(token: &Token) -> Mediate {
 
 #[cfg(test)]
 mod tests {
-	use crate::{encode, encode_function, util::pad_u32, Token};
+	use crate::{encode, encode_function, encode_params, util::pad_u32, ErrorKind, ParamKind, PathSegment, Token};
 	use hex_literal::hex;
 
 	#[test]
@@ -519,6 +587,40 @@ mod tests {
 		assert_eq!(encoded, expected);
 	}
 
+	#[test]
+	fn encode_params_signed_int() {
+		let minus_one = hex!("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+		let kinds = [ParamKind::Int(8), ParamKind::Array(Box::new(ParamKind::Int(16)))];
+
+		let tokens = [Token::from_i64(-1), Token::Array(vec![Token::from_i64(-32768), Token::from_i128(32767)])];
+		assert_eq!(encode_params(&kinds[..1], &tokens[..1]).unwrap(), minus_one);
+		assert_eq!(encode_params(&kinds, &tokens).unwrap(), encode(&tokens));
+
+		// positive values keep their encoding
+		assert_eq!(
+			encode_params(&[ParamKind::Int(8)], &[Token::Int(4.into())]).unwrap(),
+			encode(&[Token::Int(4.into())])
+		);
+	}
+
+	#[test]
+	fn encode_params_rejects_invalid_tokens() {
+		let kinds = [ParamKind::Bool, ParamKind::Array(Box::new(ParamKind::Int(8)))];
+
+		let error =
+			encode_params(&kinds, &[Token::Bool(true), Token::Array(vec![Token::Int(0x100.into())])]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::ValueOutOfRange);
+		assert_eq!(error.path.segments(), &[PathSegment::Input(1), PathSegment::Element(0)]);
+
+		let error = encode_params(&kinds, &[Token::Uint(1.into()), Token::Array(vec![])]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::TypeMismatch);
+		assert!(encode_params(&kinds, &[Token::Bool(true)]).is_err());
+
+		// raw two's complement is not sign-extended
+		let error = encode_params(&[ParamKind::Int(8)], &[Token::Int(200.into())]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::ValueOutOfRange);
+	}
+
 	#[test]
 	fn encode_bool() {
 		let encoded = encode(&vec![Token::Bool(true)]);
@@ -842,4 +944,22 @@ mod tests {
 		).to_vec();
 		assert_eq!(encoded, expected);
 	}
+
+	#[test]
+	fn encode_static_tuple_in_dynamic_tuple() {
+		let inner = Token::Tuple(vec![Token::Uint(1.into()), Token::Uint(2.into())]);
+		let outer = Token::Tuple(vec![inner, Token::Bytes(vec![])]);
+		let encoded = encode(&[outer]);
+		let expected = hex!(
+			"
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000060
+			0000000000000000000000000000000000000000000000000000000000000000
+		"
+		)
+		.to_vec();
+		assert_eq!(encoded, expected);
+	}
 }
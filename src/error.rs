@@ -0,0 +1,211 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Decoding and encoding errors.
+
+use core::fmt;
+
+use crate::std::Vec;
+
+/// Reason a decode failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+	/// The data is empty, but the params need at least one word.
+	EmptyData,
+	/// The data length is not a multiple of 32 bytes.
+	UnalignedData,
+	/// An offset, length or param points past the end of the data.
+	OutOfBounds,
+	/// An offset or length does not fit in 32 bits.
+	LengthOverflow,
+	/// An offset is not a multiple of 32 bytes.
+	MisalignedOffset,
+	/// A bool word is neither 0 nor 1.
+	InvalidBool,
+	/// Non-zero bytes where zero padding or sign extension is required.
+	DirtyPadding,
+	/// The heads and tails are not laid out the way the canonical encoding would lay them out.
+	NonCanonical,
+	/// Decoding would exceed the configured `DecodeLimits`, or a type nests deeper than the default ones allow.
+	LimitExceeded,
+	/// The first topic is not the hash of the event signature.
+	SignatureMismatch,
+	/// The number of topics does not match the number of indexed params.
+	TopicCountMismatch,
+	/// The calldata does not start with the function selector.
+	SelectorMismatch,
+	/// A type string does not name a valid Solidity type.
+	InvalidType,
+	/// A signature is not of the form `name(params)`.
+	InvalidSignature,
+	/// A token does not match the param type it is encoded as.
+	TypeMismatch,
+	/// A value does not fit in the width of its param type.
+	ValueOutOfRange,
+	/// A log would need more than four topics.
+	TooManyTopics,
+	/// A path does not lead to a value of the params.
+	InvalidPath,
+	/// A string is not valid UTF-8.
+	InvalidUtf8,
+	/// More than one anonymous event decodes the log.
+	AmbiguousEvent,
+}
+
+impl fmt::Display for ErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let reason = match self {
+			ErrorKind::EmptyData => "empty data",
+			ErrorKind::UnalignedData => "data length is not a multiple of 32",
+			ErrorKind::OutOfBounds => "out of bounds",
+			ErrorKind::LengthOverflow => "length overflow",
+			ErrorKind::MisalignedOffset => "misaligned offset",
+			ErrorKind::InvalidBool => "invalid bool",
+			ErrorKind::DirtyPadding => "dirty padding",
+			ErrorKind::NonCanonical => "non-canonical encoding",
+			ErrorKind::LimitExceeded => "decode limit exceeded",
+			ErrorKind::SignatureMismatch => "signature mismatch",
+			ErrorKind::TopicCountMismatch => "topic count mismatch",
+			ErrorKind::SelectorMismatch => "selector mismatch",
+			ErrorKind::InvalidType => "invalid type",
+			ErrorKind::InvalidSignature => "invalid signature",
+			ErrorKind::TypeMismatch => "type mismatch",
+			ErrorKind::ValueOutOfRange => "value out of range",
+			ErrorKind::TooManyTopics => "too many topics",
+			ErrorKind::InvalidPath => "invalid path",
+			ErrorKind::InvalidUtf8 => "invalid utf-8",
+			ErrorKind::AmbiguousEvent => "ambiguous event",
+		};
+		f.write_str(reason)
+	}
+}
+
+/// One step on the way from the list of params down to a nested value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSegment {
+	/// Top-level param at the given index.
+	Input(usize),
+	/// Tuple component at the given index.
+	Component(usize),
+	/// Array element at the given index.
+	Element(usize),
+}
+
+/// Location of a value inside a list of params, outermost step first.
+///
+/// Displays like `inputs[2].components[1][3]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParamPath(Vec<PathSegment>);
+
+impl ParamPath {
+	/// Returns the steps of the path, outermost first.
+	pub fn segments(&self) -> &[PathSegment] {
+		&self.0
+	}
+
+	/// Returns whether the path points at nothing in particular.
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+}
+
+impl fmt::Display for ParamPath {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for segment in &self.0 {
+			match segment {
+				PathSegment::Input(i) => write!(f, "inputs[{}]", i)?,
+				PathSegment::Component(i) => write!(f, ".components[{}]", i)?,
+				PathSegment::Element(i) => write!(f, "[{}]", i)?,
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Decoding error, telling what went wrong and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+	/// What went wrong.
+	pub kind: ErrorKind,
+	/// The param being decoded when it went wrong. Empty if the failure is not
+	/// specific to a single param.
+	pub path: ParamPath,
+	/// Byte offset into the decoded buffer at which the failure was detected.
+	pub offset: Option<usize>,
+}
+
+impl Error {
+	/// Creates an error not tied to any position.
+	pub fn new(kind: ErrorKind) -> Self {
+		Error { kind, path: ParamPath::default(), offset: None }
+	}
+
+	/// Creates an error detected at the given byte offset.
+	pub fn at(kind: ErrorKind, offset: usize) -> Self {
+		Error { kind, path: ParamPath::default(), offset: Some(offset) }
+	}
+
+	/// Prepends `segment` to the path, used while the error bubbles up out of nested params.
+	pub(crate) fn within(mut self, segment: PathSegment) -> Self {
+		self.path.0.insert(0, segment);
+		self
+	}
+
+	/// Moves the byte offset by `delta`, used when decoding a part of a larger buffer.
+	pub(crate) fn shifted(mut self, delta: usize) -> Self {
+		self.offset = self.offset.map(|offset| offset + delta);
+		self
+	}
+
+	/// Rewrites the top-level input index, used when decoding a subset of the params.
+	pub(crate) fn map_input<F: FnOnce(usize) -> usize>(mut self, f: F) -> Self {
+		if let Some(PathSegment::Input(i)) = self.path.0.first_mut() {
+			*i = f(*i);
+		}
+		self
+	}
+}
+
+impl From<ErrorKind> for Error {
+	fn from(kind: ErrorKind) -> Self {
+		Error::new(kind)
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.kind)?;
+		if !self.path.is_empty() {
+			write!(f, " at {}", self.path)?;
+		}
+		if let Some(offset) = self.offset {
+			write!(f, " (byte {})", offset)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+	use super::{Error, ErrorKind, PathSegment};
+
+	#[test]
+	fn test_display() {
+		let error = Error::at(ErrorKind::InvalidBool, 96)
+			.within(PathSegment::Element(3))
+			.within(PathSegment::Component(1))
+			.within(PathSegment::Input(2));
+
+		assert_eq!(error.path.to_string(), "inputs[2].components[1][3]");
+		assert_eq!(error.to_string(), "invalid bool at inputs[2].components[1][3] (byte 96)");
+		assert_eq!(Error::new(ErrorKind::SignatureMismatch).to_string(), "signature mismatch");
+	}
+}
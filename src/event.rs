@@ -9,11 +9,180 @@
 
 //! Contract event.
 use crate::std::BTreeMap;
-use crate::std::Vec;
+use crate::std::{String, Vec};
 use tiny_keccak::{Hasher, Keccak};
 
-use crate::{decode, Error, Param, ParamKind, Token, H256};
+use crate::{
+	decoder::{decode_shapes, head_words},
+	encoder::conform,
+	param::find_token,
+	tokenizable,
+};
+use crate::{
+	encode, signature, util::keccak256, DecodeLimits, DecodedLog, DecodedParams, Error, ErrorKind, Param, ParamKind,
+	PathSegment, StaticEvent, StaticParam, Token, Tokenizable, H256,
+};
+use ethabi_decode_syntax::{Shape, TypeShape};
 
+/// Maximum number of topics of a log, as for the EVM's `LOG4`.
+pub(crate) const MAX_TOPICS: usize = 4;
+
+/// Appends the in-place encoding Solidity hashes for indexed dynamic values:
+/// no length prefixes, and values nested in arrays or tuples padded to a multiple of 32 bytes.
+fn encode_hashed_topic(token: &Token, pad: bool, out: &mut Vec<u8>) {
+	match token {
+		Token::Bytes(bytes) | Token::String(bytes) => {
+			out.extend_from_slice(bytes);
+			if pad {
+				out.resize(out.len() + bytes.len().next_multiple_of(32) - bytes.len(), 0);
+			}
+		}
+		Token::Array(tokens) | Token::FixedArray(tokens) | Token::Tuple(tokens) => {
+			tokens.iter().for_each(|token| encode_hashed_topic(token, true, out))
+		}
+		_ => out.extend(encode(core::slice::from_ref(token))),
+	}
+}
+
+/// Returns whether indexed values of `kind` are logged as their hash: `string`,
+/// `bytes`, arrays and tuples, see
+/// https://solidity.readthedocs.io/en/develop/abi-spec.html#encoding-of-indexed-event-parameters
+pub(crate) fn is_hashed_topic<K: TypeShape>(kind: &K) -> bool {
+	matches!(kind.shape(), Shape::String | Shape::Bytes | Shape::Array(_) | Shape::FixedArray(..) | Shape::Tuple(_))
+}
+
+/// Event param, as needed to encode and decode logs. Implemented by `Param` and `StaticParam`.
+pub(crate) trait EventParam {
+	type Kind: TypeShape;
+
+	fn kind(&self) -> &Self::Kind;
+
+	fn indexed(&self) -> bool;
+}
+
+impl EventParam for Param {
+	type Kind = ParamKind;
+
+	fn kind(&self) -> &ParamKind {
+		&self.kind
+	}
+
+	fn indexed(&self) -> bool {
+		self.indexed
+	}
+}
+
+/// Encodes a single indexed value as a topic, hashing it if `is_hashed_topic`.
+fn encode_topic<K: TypeShape>(kind: &K, token: &Token) -> Result<H256, Error> {
+	let token = conform(kind, token)?;
+	if is_hashed_topic(kind) {
+		let mut encoded = Vec::new();
+		encode_hashed_topic(&token, false, &mut encoded);
+		Ok(keccak256(&encoded).into())
+	} else {
+		Ok(H256::from_slice(&encode(core::slice::from_ref(&token))))
+	}
+}
+
+/// Returns the index in `inputs` of the `n`th param with the given indexed flag.
+fn input_index<P: EventParam>(inputs: &[P], indexed: bool, n: usize) -> usize {
+	inputs.iter().enumerate().filter(|(_, p)| p.indexed() == indexed).nth(n).map_or(n, |(i, _)| i)
+}
+
+/// See `Event::encode_topics`, with `topic` the signature topic of non-anonymous events.
+pub(crate) fn encode_log_topics<P: EventParam>(
+	inputs: &[P],
+	topic: Option<H256>,
+	indexed: &[Option<Token>],
+) -> Result<Vec<Option<H256>>, Error> {
+	let indices = inputs.iter().enumerate().filter(|(_, p)| p.indexed()).map(|(i, _)| i);
+	if indices.clone().count() != indexed.len() {
+		return Err(ErrorKind::TopicCountMismatch.into());
+	}
+
+	let mut topics = Vec::with_capacity(indexed.len() + 1);
+	topics.extend(topic.map(Some));
+	for (i, token) in indices.zip(indexed) {
+		let topic = match token {
+			Some(token) => Some(encode_topic(inputs[i].kind(), token).map_err(|e| e.within(PathSegment::Input(i)))?),
+			None => None,
+		};
+		topics.push(topic);
+	}
+	Ok(topics)
+}
+
+/// See `Event::encode_log`, with `topic` the signature topic of non-anonymous events.
+pub(crate) fn encode_event_log<P: EventParam>(
+	inputs: &[P],
+	topic: Option<H256>,
+	tokens: &[Token],
+) -> Result<(Vec<H256>, Vec<u8>), Error> {
+	if tokens.len() != inputs.len() {
+		return Err(ErrorKind::TypeMismatch.into());
+	}
+
+	let mut topics = Vec::new();
+	topics.extend(topic);
+	let mut data_tokens = Vec::new();
+	for (i, (param, token)) in inputs.iter().zip(tokens).enumerate() {
+		if !param.indexed() {
+			data_tokens.push(conform(param.kind(), token).map_err(|e| e.within(PathSegment::Input(i)))?);
+		} else if topics.len() == MAX_TOPICS {
+			return Err(ErrorKind::TooManyTopics.into());
+		} else {
+			topics.push(encode_topic(param.kind(), token).map_err(|e| e.within(PathSegment::Input(i)))?);
+		}
+	}
+
+	Ok((topics, encode(&data_tokens)))
+}
+
+/// See `Event::decode_with_limits` and `Event::decode_strict_with_limits`, with
+/// `topic` the signature topic of non-anonymous events.
+///
+/// Errors are reported against the position of the param in `inputs`. Their
+/// byte offset is relative to `data` for non-indexed params, and to the
+/// concatenated topics (without the signature topic) for indexed ones.
+pub(crate) fn decode_event_log<P: EventParam>(
+	inputs: &[P],
+	topic: Option<H256>,
+	topics: Vec<H256>,
+	data: Vec<u8>,
+	limits: &DecodeLimits,
+	strict: bool,
+) -> Result<Vec<Token>, Error> {
+	let topics = match (topic, topics.split_first()) {
+		(None, _) => &topics[..],
+		(Some(topic), Some((first, rest))) if *first == topic => rest,
+		(Some(_), Some(_)) => return Err(ErrorKind::SignatureMismatch.into()),
+		(Some(_), None) => return Err(ErrorKind::TopicCountMismatch.into()),
+	};
+
+	let topic_params = inputs.iter().enumerate().filter(|(_, p)| p.indexed());
+	if topic_params.clone().count() != topics.len() {
+		return Err(ErrorKind::TopicCountMismatch.into());
+	}
+
+	// every topic is a single word, hashed values are taken as their `bytes32` topic
+	let mut topic_tokens = Vec::with_capacity(topics.len());
+	for (n, ((i, param), topic)) in topic_params.zip(topics).enumerate() {
+		let token = match is_hashed_topic(param.kind()) {
+			true => Token::FixedBytes(topic.as_bytes().to_vec()),
+			false => decode_shapes(core::iter::once(param.kind()), topic.as_bytes(), limits, strict)
+				.map_err(|e| e.map_input(|_| i).shifted(n * 32))?
+				.remove(0),
+		};
+		topic_tokens.push(token);
+	}
+
+	let data_kinds = inputs.iter().filter(|p| !p.indexed()).map(EventParam::kind);
+	let data_tokens =
+		decode_shapes(data_kinds, &data, limits, strict).map_err(|e| e.map_input(|n| input_index(inputs, false, n)))?;
+
+	let (mut topic_tokens, mut data_tokens) = (topic_tokens.into_iter(), data_tokens.into_iter());
+	Ok(inputs.iter().filter_map(|p| if p.indexed() { topic_tokens.next() } else { data_tokens.next() }).collect())
+}
 
 /// Contract event.
 #[derive(Clone, Debug, PartialEq)]
@@ -35,9 +204,41 @@ impl<'a> Event<'a> {
 		result.into()
 	}
 
-	/// Returns all params of the event.
-	fn indexed_params(&self, indexed: bool) -> Vec<Param> {
-		self.inputs.iter().filter(|p| p.indexed == indexed).cloned().collect()
+	/// Returns the name of the event, see `ethabi_decode::name`.
+	pub fn name(&self) -> &'a str {
+		signature::name(self.signature)
+	}
+
+	/// Returns the hash of the signature, the first topic of logs of non-anonymous events.
+	pub fn topic(&self) -> H256 {
+		self.signature_keccak256()
+	}
+
+	/// Checks that `signature` is the canonical signature for `inputs`, see `ethabi_decode::check_signature`.
+	pub fn check_signature(&self) -> Result<(), Error> {
+		signature::check_signature(self.signature, self.inputs.iter().map(|p| &p.kind))
+	}
+
+	/// Checks the event definition: every input type with `ParamKind::validate`,
+	/// the number of indexed inputs against the four topics a log can hold, and,
+	/// unless the event is anonymous, `signature` with `check_signature`.
+	///
+	/// Errors in an input type are located at that input. Fails with
+	/// `ErrorKind::TooManyTopics` if there are too many indexed inputs.
+	pub fn validate(&self) -> Result<(), Error> {
+		for (i, param) in self.inputs.iter().enumerate() {
+			param.kind.validate().map_err(|e| e.within(PathSegment::Input(i)))?;
+		}
+
+		let topics = self.indices(true).len() + usize::from(!self.anonymous);
+		if topics > MAX_TOPICS {
+			return Err(ErrorKind::TooManyTopics.into());
+		}
+
+		match self.anonymous {
+			true => Ok(()),
+			false => self.check_signature(),
+		}
 	}
 
 	/// Returns indices of all params of the event
@@ -45,76 +246,265 @@ impl<'a> Event<'a> {
 		self.inputs.iter().enumerate().filter(|(_, p)| p.indexed == indexed).map(|(i, _)| i).collect()
 	}
 
-	// Converts param types for indexed parameters to bytes32 where appropriate
-	// This applies to strings, arrays, structs and bytes to follow the encoding of
-	// these indexed param types according to
-	// https://solidity.readthedocs.io/en/develop/abi-spec.html#encoding-of-indexed-event-parameters
-	fn convert_topic_param_type(&self, kind: &ParamKind) -> ParamKind {
-		match kind {
-			ParamKind::String
-			| ParamKind::Bytes
-			| ParamKind::Array(_)
-			| ParamKind::FixedArray(_, _)
-			| ParamKind::Tuple(_) => ParamKind::FixedBytes(32),
-			_ => kind.clone(),
+	/// Returns the topic logs of the event start with, `None` if it is anonymous.
+	fn signature_topic(&self) -> Option<H256> {
+		match self.anonymous {
+			true => None,
+			false => Some(self.signature_keccak256()),
 		}
 	}
 
+	/// Encodes the topics of a log filter matching the given indexed values.
+	///
+	/// `indexed` holds one value per indexed param, in order, with `None` matching
+	/// any value. Unless the event is anonymous, the signature hash comes first.
+	/// Indexed `string`, `bytes`, arrays and tuples are hashed the way Solidity does.
+	pub fn encode_topics(&self, indexed: &[Option<Token>]) -> Result<Vec<Option<H256>>, Error> {
+		encode_log_topics(self.inputs, self.signature_topic(), indexed)
+	}
+
+	/// Encodes a complete log the way Solidity's `emit` does: the topics of the
+	/// indexed values, and the ABI encoding of the other values as data.
+	///
+	/// `tokens` holds one value per param in `inputs`. Decoding the result with
+	/// `decode` yields `tokens` back, except that hashed indexed values come back
+	/// as their `bytes32` topic. Values are checked as by `encode_params`. Fails with
+	/// `ErrorKind::TooManyTopics` if the log would need more than the four topics
+	/// the EVM supports.
+	pub fn encode_log(&self, tokens: &[Token]) -> Result<(Vec<H256>, Vec<u8>), Error> {
+		encode_event_log(self.inputs, self.signature_topic(), tokens)
+	}
+
+	/// Decodes an event log into a Rust type, a tuple with one element per param in `inputs`.
+	///
+	/// Hashed indexed values come back as their `bytes32` topic, see `H256`.
+	pub fn decode_as<T: Tokenizable>(&self, topics: Vec<H256>, data: Vec<u8>) -> Result<T, Error> {
+		tokenizable::from_tokens(self.decode(topics, data)?)
+	}
+
+	/// Returns the input named `name` among tokens decoded by `decode`. A dotted
+	/// name like "order.amount" steps into tuple components.
+	pub fn find_input<'t>(&self, tokens: &'t [Token], name: &str) -> Option<&'t Token> {
+		find_token(self.inputs.iter().map(|p| &p.names), tokens, name)
+	}
+
+	/// Decodes an event log like `decode`, keeping the name, type and indexed flag
+	/// of each value, and whether it is only a hash.
+	pub fn decode_named(&self, topics: Vec<H256>, data: Vec<u8>) -> Result<DecodedLog, Error> {
+		let params = DecodedParams::from_event(self.inputs, self.decode(topics, data)?);
+		Ok(DecodedLog { signature: String::from(self.signature), params })
+	}
+
+	/// Decodes an event log using the default `DecodeLimits`.
 	pub fn decode(&self, topics: Vec<H256>, data: Vec<u8>) -> Result<Vec<Token>, Error> {
+		self.decode_with_limits(topics, data, &DecodeLimits::default())
+	}
 
-		// Take first topic if event is not anonymous
-		let to_skip = if self.anonymous {
-			0
-		} else {
-			// verify
-			let event_signature = topics.get(0).ok_or(Error::InvalidData)?;
-			if event_signature != &self.signature_keccak256() {
-				return Err(Error::InvalidData.into());
-			}
-			1
-		};
+	/// Decodes an event log, failing with `ErrorKind::LimitExceeded` if the topics or
+	/// the data would decode to more than `limits` allows.
+	pub fn decode_with_limits(
+		&self,
+		topics: Vec<H256>,
+		data: Vec<u8>,
+		limits: &DecodeLimits,
+	) -> Result<Vec<Token>, Error> {
+		decode_event_log(self.inputs, self.signature_topic(), topics, data, limits, false)
+	}
+
+	/// Decodes an event log, rejecting non-canonical encodings of both the topics
+	/// and the data. See `decode_strict` for the rules applied.
+	pub fn decode_strict(&self, topics: Vec<H256>, data: Vec<u8>) -> Result<Vec<Token>, Error> {
+		self.decode_strict_with_limits(topics, data, &DecodeLimits::default())
+	}
 
-		let topics_len = topics.len();
+	/// Same as `decode_strict`, but honouring the given `limits`.
+	pub fn decode_strict_with_limits(
+		&self,
+		topics: Vec<H256>,
+		data: Vec<u8>,
+		limits: &DecodeLimits,
+	) -> Result<Vec<Token>, Error> {
+		decode_event_log(self.inputs, self.signature_topic(), topics, data, limits, true)
+	}
+}
+
+/// Rust type standing for the values of an event, usually derived with `#[derive(EthEvent)]`.
+pub trait EthEvent: Sized {
+	/// Event signature. Like "Transfer(address,address,uint256)".
+	const SIGNATURE: &'static str;
+	/// Hash of `SIGNATURE`, the first topic of logs of non-anonymous events.
+	const TOPIC: H256;
+	/// Event input, one param per value.
+	const INPUTS: &'static [StaticParam];
+	/// If anonymous, logs do not start with `TOPIC`.
+	const ANONYMOUS: bool;
+	/// Definition of the event, made of the consts above.
+	const EVENT: StaticEvent = StaticEvent {
+		signature: Self::SIGNATURE,
+		topic: Self::TOPIC,
+		inputs: Self::INPUTS,
+		anonymous: Self::ANONYMOUS,
+	};
+
+	/// Returns the definition of the event as an `OwnedEvent`.
+	fn event() -> OwnedEvent {
+		Self::EVENT.to_owned_event()
+	}
+
+	/// Converts decoded tokens, one per param in `INPUTS`.
+	fn from_tokens(tokens: Vec<Token>) -> Result<Self, Error>;
+
+	/// Converts into tokens, one per param in `INPUTS`.
+	fn into_tokens(self) -> Vec<Token>;
+
+	/// Decodes a log of the event, see `StaticEvent::decode`.
+	fn decode_log(topics: Vec<H256>, data: Vec<u8>) -> Result<Self, Error> {
+		Self::from_tokens(Self::EVENT.decode(topics, data)?)
+	}
+
+	/// Encodes a log of the event, see `StaticEvent::encode_log`.
+	fn encode_log(self) -> Result<(Vec<H256>, Vec<u8>), Error> {
+		Self::EVENT.encode_log(&self.into_tokens())
+	}
+}
+
+/// Event owning its definition, with the signature derived from its name and inputs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnedEvent {
+	/// Event signature. Like "Foo(int32,bytes)".
+	pub signature: String,
+	/// Event input.
+	pub inputs: Vec<Param>,
+	/// If anonymous, event cannot be found using `from` filter.
+	pub anonymous: bool,
+}
 
-		// obtains all params info
-		let topic_params = self.indexed_params(true);
-		let data_params = self.indexed_params(false);
-		let topic_params_indices = self.indices(true);
-		let data_params_indices = self.indices(false);
+impl OwnedEvent {
+	/// Creates an event, computing its canonical signature from `name` and `inputs`.
+	pub fn new(name: &str, inputs: Vec<Param>, anonymous: bool) -> Self {
+		let signature = signature::signature(name, inputs.iter().map(|p| &p.kind));
+		OwnedEvent { signature, inputs, anonymous }
+	}
 
+	/// Borrows the event, to decode logs with it.
+	pub fn as_event(&self) -> Event<'_> {
+		Event { signature: &self.signature, inputs: &self.inputs, anonymous: self.anonymous }
+	}
 
-		let topic_types =
-			topic_params.iter().map(|p| self.convert_topic_param_type(&p.kind)).collect::<Vec<ParamKind>>();
+	/// Returns whether a log with `topics` and `data` has the shape of a log of this
+	/// anonymous event: one topic per indexed input, and data at least as long as
+	/// the heads of the other inputs, or exactly as long if they are all static.
+	fn fits(&self, topics: &[H256], data: &[u8]) -> bool {
+		let data_params = self.inputs.iter().filter(|p| !p.indexed);
+		let head = data_params.clone().fold(0usize, |acc, p| acc.saturating_add(head_words(&p.kind)));
+		let head = head.saturating_mul(32);
+		let fits_data = match data_params.clone().any(|p| p.kind.is_dynamic()) {
+			true => data.len() >= head && data.len() % 32 == 0,
+			false => data.len() == head,
+		};
+		fits_data && data_params.count() + topics.len() == self.inputs.len()
+	}
+}
 
-		let flat_topics = topics.into_iter().skip(to_skip).flat_map(|t| t.as_ref().to_vec()).collect::<Vec<u8>>();
+/// Set of known events, to find which one a log was emitted for.
+///
+/// Events are indexed by their signature topic, so logs of non-anonymous events
+/// find their definition in `O(log n)`. Logs whose first topic matches none are
+/// tried against the anonymous events of the right shape, see `decode`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventRegistry {
+	events: BTreeMap<H256, OwnedEvent>,
+	anonymous: Vec<OwnedEvent>,
+}
 
-		let topic_tokens = decode(&topic_types, &flat_topics)?;
+impl EventRegistry {
+	/// Creates an empty registry.
+	pub fn new() -> Self {
+		Self::default()
+	}
 
-		// topic may be only a 32 bytes encoded token
-		if topic_tokens.len() != topics_len - to_skip {
-			return Err(Error::InvalidData);
+	/// Adds an event, replacing the one with the same topic if any. Anonymous
+	/// events are kept in the order they are added.
+	pub fn insert(&mut self, event: OwnedEvent) {
+		match event.anonymous {
+			true => self.anonymous.push(event),
+			false => {
+				self.events.insert(event.as_event().topic(), event);
+			}
 		}
+	}
 
-		let topics_named_tokens = topic_params_indices.into_iter().zip(topic_tokens.into_iter());
+	/// Returns the non-anonymous event with the given signature topic.
+	pub fn get(&self, topic: &H256) -> Option<&OwnedEvent> {
+		self.events.get(topic)
+	}
 
-		let data_types = data_params.iter().map(|p| p.kind.clone()).collect::<Vec<ParamKind>>();
-		let data_tokens = decode(&data_types, &data)?;
-		let data_named_tokens = data_params_indices.into_iter().zip(data_tokens.into_iter());
+	/// Returns all the events, ordered by topic, the anonymous ones last.
+	pub fn iter(&self) -> impl Iterator<Item = &OwnedEvent> {
+		self.events.values().chain(&self.anonymous)
+	}
+
+	/// Returns the anonymous events a log could have been emitted for, judging by
+	/// its number of topics and the length of its data.
+	pub fn candidates(&self, topics: &[H256], data: &[u8]) -> Vec<&OwnedEvent> {
+		self.anonymous.iter().filter(|event| event.fits(topics, data)).collect()
+	}
+
+	/// Decodes a log with the event it was emitted for, returning that event along
+	/// with the decoded tokens.
+	///
+	/// A log whose first topic is the topic of a known event is decoded with that
+	/// event. Any other log is decoded with each of the `candidates`, and must be
+	/// decoded by exactly one of them. Fails with `ErrorKind::AmbiguousEvent` if
+	/// several decode it, and with `ErrorKind::SignatureMismatch` if none does,
+	/// unless there was a single candidate, whose decoding error is returned.
+	pub fn decode(&self, topics: Vec<H256>, data: Vec<u8>) -> Result<(&OwnedEvent, Vec<Token>), Error> {
+		if let Some(event) = topics.first().and_then(|topic| self.get(topic)) {
+			return Ok((event, event.as_event().decode(topics, data)?));
+		}
+
+		let candidates = self.candidates(&topics, &data);
+		if let [event] = candidates[..] {
+			return Ok((event, event.as_event().decode(topics, data)?));
+		}
 
-		let named_tokens = topics_named_tokens.chain(data_named_tokens).collect::<BTreeMap<usize, Token>>();
+		let mut decoded = None;
+		for event in candidates {
+			if let Ok(tokens) = event.as_event().decode(topics.clone(), data.clone()) {
+				if decoded.is_some() {
+					return Err(ErrorKind::AmbiguousEvent.into());
+				}
+				decoded = Some((event, tokens));
+			}
+		}
+		decoded.ok_or_else(|| ErrorKind::SignatureMismatch.into())
+	}
 
-		let tokens: Vec<Token> =
-			self.inputs.iter().enumerate().map(|t| t.0).map(|i| named_tokens[&i].clone()).collect();
+	/// Decodes a log like `decode`, keeping the names of the values, see `Event::decode_named`.
+	pub fn decode_named(&self, topics: Vec<H256>, data: Vec<u8>) -> Result<DecodedLog, Error> {
+		let (event, tokens) = self.decode(topics, data)?;
+		let params = DecodedParams::from_event(&event.inputs, tokens);
+		Ok(DecodedLog { signature: event.signature.clone(), params })
+	}
+}
 
-		Ok(tokens)
+impl FromIterator<OwnedEvent> for EventRegistry {
+	fn from_iter<I: IntoIterator<Item = OwnedEvent>>(events: I) -> Self {
+		let mut registry = EventRegistry::new();
+		events.into_iter().for_each(|event| registry.insert(event));
+		registry
 	}
 }
 
 #[cfg(test)]
 mod tests {
 
-	use crate::{token::Token, Event, Param, ParamKind, H256};
+	use crate::{
+		encode, token::Token, Address, ErrorKind, Event, EventRegistry, OwnedEvent, Param, ParamKind, PathSegment,
+		H256, U256,
+	};
 	use hex::FromHex;
+	use hex_literal::hex;
 	use tiny_keccak::{Hasher, Keccak};
 
 	fn keccak256(data: &str) -> H256 {
@@ -129,13 +519,14 @@ mod tests {
 	fn test_decoding_event() {
 		let event = Event {
 			signature: "foo(int256,int256,address,address,string,int256[],address[5])",
-			inputs: &[Param { kind: ParamKind::Int(256), indexed: false },
-				Param { kind: ParamKind::Int(256), indexed: true },
-				Param { kind: ParamKind::Address, indexed: false },
-				Param { kind: ParamKind::Address, indexed: true },
-				Param { kind: ParamKind::String, indexed: true },
-				Param { kind: ParamKind::Array(Box::new(ParamKind::Int(256))), indexed: true },
-				Param { kind: ParamKind::FixedArray(Box::new(ParamKind::Address), 5), indexed: true },
+			inputs: &[
+				Param::new(ParamKind::Int(256), false),
+				Param::new(ParamKind::Int(256), true),
+				Param::new(ParamKind::Address, false),
+				Param::new(ParamKind::Address, true),
+				Param::new(ParamKind::String, true),
+				Param::new(ParamKind::Array(Box::new(ParamKind::Int(256))), true),
+				Param::new(ParamKind::FixedArray(Box::new(ParamKind::Address), 5), true),
 			],
 			anonymous: false,
 		};
@@ -177,4 +568,363 @@ mod tests {
 			]
 		)
 	}
+
+	#[test]
+	fn test_decoding_event_strict() {
+		let event = Event {
+			signature: "bar(address,bool)",
+			inputs: &[Param::new(ParamKind::Address, true), Param::new(ParamKind::Bool, false)],
+			anonymous: false,
+		};
+
+		let data: Vec<u8> = "0000000000000000000000000000000000000000000000000000000000000001".from_hex().unwrap();
+		let clean: Vec<H256> = vec![
+			keccak256("bar(address,bool)"),
+			"0000000000000000000000001111111111111111111111111111111111111111".parse().unwrap(),
+		];
+		let dirty: Vec<H256> = vec![
+			keccak256("bar(address,bool)"),
+			"ff00000000000000000000001111111111111111111111111111111111111111".parse().unwrap(),
+		];
+
+		assert!(event.decode_strict(clean, data.clone()).is_ok());
+		assert!(event.decode(dirty.clone(), data.clone()).is_ok());
+		assert!(event.decode_strict(dirty, data).is_err());
+	}
+
+	#[test]
+	fn test_decoding_event_errors() {
+		let event = Event {
+			signature: "baz(uint256,address,bool)",
+			inputs: &[
+				Param::new(ParamKind::Uint(256), false),
+				Param::new(ParamKind::Address, true),
+				Param::new(ParamKind::Bool, false),
+			],
+			anonymous: false,
+		};
+		let address: H256 = "0000000000000000000000001111111111111111111111111111111111111111".parse().unwrap();
+		let data: Vec<u8> = concat!(
+			"0000000000000000000000000000000000000000000000000000000000000003",
+			"0000000000000000000000000000000000000000000000000000000000000100"
+		)
+		.from_hex()
+		.unwrap();
+
+		let error = event.decode(vec![keccak256("baz(uint256,address)"), address], data.clone()).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::SignatureMismatch);
+
+		let error = event.decode(vec![keccak256("baz(uint256,address,bool)")], data.clone()).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::TopicCountMismatch);
+
+		let error = event.decode(vec![], data.clone()).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::TopicCountMismatch);
+
+		// the bool is the second data param but the third input
+		let error = event.decode(vec![keccak256("baz(uint256,address,bool)"), address], data).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::InvalidBool);
+		assert_eq!(error.path.segments(), &[PathSegment::Input(2)]);
+		assert_eq!(error.offset, Some(32));
+
+		// topic offsets count from the first topic after the signature
+		let inputs = [Param::new(ParamKind::Bool, true), Param::new(ParamKind::Bool, true)];
+		let event = Event { signature: "qux(bool,bool)", inputs: &inputs, anonymous: true };
+		let topics = vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)];
+		let error = event.decode_strict(topics, vec![]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::InvalidBool);
+		assert_eq!(error.path.segments(), &[PathSegment::Input(1)]);
+		assert_eq!(error.offset, Some(32));
+	}
+
+	#[test]
+	fn test_check_signature() {
+		let inputs = [Param::new(ParamKind::Address, true), Param::new(ParamKind::Uint(256), false)];
+
+		let event = Event { signature: "Deposit(address,uint256)", inputs: &inputs, anonymous: false };
+		assert!(event.check_signature().is_ok());
+
+		let event = Event { signature: "Deposit(address,uint128)", inputs: &inputs, anonymous: false };
+		let error = event.check_signature().unwrap_err();
+		assert_eq!(error.kind, ErrorKind::SignatureMismatch);
+		assert_eq!(error.path.segments(), &[PathSegment::Input(1)]);
+	}
+
+	#[test]
+	fn test_owned_event() {
+		let event = OwnedEvent::new(
+			"Deposit",
+			vec![Param::new(ParamKind::Address, true), Param::new(ParamKind::Uint(256), false)],
+			false,
+		);
+		assert_eq!(event.signature, "Deposit(address,uint256)");
+		assert!(event.as_event().check_signature().is_ok());
+
+		let topics = vec![
+			keccak256("Deposit(address,uint256)"),
+			"0000000000000000000000001111111111111111111111111111111111111111".parse().unwrap(),
+		];
+		let data = "0000000000000000000000000000000000000000000000000000000000000007".from_hex().unwrap();
+
+		assert_eq!(
+			event.as_event().decode(topics, data).unwrap(),
+			vec![Token::Address([0x11u8; 20].into()), Token::Uint(7.into())]
+		);
+	}
+
+	#[test]
+	fn test_encode_topics() {
+		let event = OwnedEvent::new(
+			"Transfer",
+			vec![
+				Param::new(ParamKind::Address, true),
+				Param::new(ParamKind::Address, true),
+				Param::new(ParamKind::Uint(256), false),
+			],
+			false,
+		);
+		let event = event.as_event();
+
+		let from = Token::Address([0x11u8; 20].into());
+		let topics = event.encode_topics(&[Some(from), None]).unwrap();
+
+		assert_eq!(
+			topics,
+			vec![
+				Some(keccak256("Transfer(address,address,uint256)")),
+				Some(H256(hex!("0000000000000000000000001111111111111111111111111111111111111111"))),
+				None,
+			]
+		);
+		assert_eq!(event.encode_topics(&[None, None]).unwrap()[1..], [None, None]);
+	}
+
+	#[test]
+	fn test_encode_hashed_topics() {
+		let tuple = ParamKind::Tuple(vec![Box::new(ParamKind::String), Box::new(ParamKind::Uint(8))]);
+		let inputs = [
+			Param::new(ParamKind::String, true),
+			Param::new(ParamKind::Array(Box::new(ParamKind::Uint(256))), true),
+			Param::new(tuple, true),
+		];
+		let event = Event { signature: "", inputs: &inputs, anonymous: true };
+
+		let values = [
+			Token::String(b"hello".to_vec()),
+			Token::Array(vec![Token::Uint(1.into()), Token::Uint(2.into())]),
+			Token::Tuple(vec![Token::String(b"hello".to_vec()), Token::Uint(3.into())]),
+		];
+		let topics = event.encode_topics(&values.clone().map(Some)).unwrap();
+
+		// strings are hashed unpadded at the top level, but padded inside arrays and tuples
+		let mut padded = b"hello".to_vec();
+		padded.resize(32, 0);
+		let tuple = [padded, encode(&[Token::Uint(3.into())])].concat();
+		let hash = |data: &[u8]| H256::from(crate::util::keccak256(data));
+
+		assert_eq!(
+			topics,
+			vec![
+				Some(hash(b"hello")),
+				Some(hash(&encode(&[Token::Uint(1.into()), Token::Uint(2.into())]))),
+				Some(hash(&tuple)),
+			]
+		);
+	}
+
+	#[test]
+	fn test_encode_topics_errors() {
+		let inputs = [Param::new(ParamKind::Bool, false), Param::new(ParamKind::Address, true)];
+		let event = Event { signature: "baz(bool,address)", inputs: &inputs, anonymous: false };
+
+		assert_eq!(event.encode_topics(&[]).unwrap_err().kind, ErrorKind::TopicCountMismatch);
+
+		let error = event.encode_topics(&[Some(Token::Bool(true))]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::TypeMismatch);
+		assert_eq!(error.path.segments(), &[PathSegment::Input(1)]);
+	}
+
+	#[test]
+	fn test_encode_log_round_trip() {
+		let inputs = [
+			Param::new(ParamKind::Int(256), false),
+			Param::new(ParamKind::Address, true),
+			Param::new(ParamKind::String, true),
+			Param::new(ParamKind::Array(Box::new(ParamKind::Bool)), false),
+		];
+		let event = Event { signature: "foo(int256,address,string,bool[])", inputs: &inputs, anonymous: false };
+
+		let tokens = vec![
+			Token::Int(3.into()),
+			Token::Address([0x11u8; 20].into()),
+			Token::String(b"hello".to_vec()),
+			Token::Array(vec![Token::Bool(true), Token::Bool(false)]),
+		];
+		let (topics, data) = event.encode_log(&tokens).unwrap();
+
+		assert_eq!(topics.len(), 3);
+		assert_eq!(topics[0], keccak256("foo(int256,address,string,bool[])"));
+		assert_eq!(data, encode(&[tokens[0].clone(), tokens[3].clone()]));
+
+		let mut expected = tokens;
+		expected[2] = Token::FixedBytes(keccak256("hello").as_bytes().to_vec());
+		assert_eq!(event.decode(topics.clone(), data.clone()).unwrap(), expected);
+		assert_eq!(event.decode_strict(topics, data).unwrap(), expected);
+	}
+
+	#[test]
+	fn test_encode_log_signed() {
+		let inputs = [Param::new(ParamKind::Int(8), true), Param::new(ParamKind::Int(64), false)];
+		let event = Event { signature: "", inputs: &inputs, anonymous: true };
+
+		let (topics, data) = event.encode_log(&[Token::from_i64(-1), Token::from_i64(-2)]).unwrap();
+		assert_eq!(topics, vec![H256::repeat_byte(0xff)]);
+
+		let tokens = event.decode_strict(topics, data).unwrap();
+		assert_eq!(tokens[0].clone().to_i64(), Some(-1));
+		assert_eq!(tokens[1].clone().to_i128(), Some(-2));
+
+		let error = event.encode_log(&[Token::Int(0x100.into()), Token::from_i128(0)]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::ValueOutOfRange);
+		assert_eq!(error.path.segments(), &[PathSegment::Input(0)]);
+	}
+
+	#[test]
+	fn test_validate() {
+		let transfer = OwnedEvent::new(
+			"Transfer",
+			vec![
+				Param::new(ParamKind::Address, true),
+				Param::new(ParamKind::Address, true),
+				Param::new(ParamKind::Uint(256), false),
+			],
+			false,
+		);
+		assert_eq!(transfer.as_event().validate(), Ok(()));
+
+		let inputs = [Param::new(ParamKind::Bool, false), Param::new(ParamKind::Uint(7), false)];
+		let error = Event { signature: "foo(bool,uint7)", inputs: &inputs, anonymous: false }.validate().unwrap_err();
+		assert_eq!(error.kind, ErrorKind::InvalidType);
+		assert_eq!(error.path.segments(), &[PathSegment::Input(1)]);
+
+		let inputs = vec![Param::new(ParamKind::Bool, true); 4];
+		let event = Event { signature: "foo(bool,bool,bool,bool)", inputs: &inputs, anonymous: false };
+		assert_eq!(event.validate().unwrap_err().kind, ErrorKind::TooManyTopics);
+		assert_eq!(Event { anonymous: true, ..event.clone() }.validate(), Ok(()));
+
+		let event = Event { signature: "foo(bool)", ..event };
+		assert_eq!(Event { anonymous: true, ..event.clone() }.validate(), Ok(()));
+		let inputs = &inputs[..3];
+		assert_eq!(Event { inputs, ..event }.validate().unwrap_err().kind, ErrorKind::SignatureMismatch);
+	}
+
+	#[test]
+	fn test_encode_log_anonymous() {
+		let inputs = vec![Param::new(ParamKind::Uint(8), true); 4];
+		let event = Event { signature: "", inputs: &inputs, anonymous: true };
+		let tokens = [Token::Uint(1.into()), Token::Uint(2.into()), Token::Uint(3.into()), Token::Uint(4.into())];
+
+		let (topics, data) = event.encode_log(&tokens).unwrap();
+		assert_eq!(topics.len(), 4);
+		assert!(data.is_empty());
+		assert_eq!(event.decode(topics, data).unwrap(), tokens);
+
+		// a non-anonymous event spends a topic on its signature
+		let event = Event { signature: "bar(uint8,uint8,uint8,uint8)", anonymous: false, ..event };
+		assert_eq!(event.encode_log(&tokens).unwrap_err().kind, ErrorKind::TooManyTopics);
+	}
+
+	#[test]
+	fn test_encode_log_errors() {
+		let inputs = [Param::new(ParamKind::Address, true), Param::new(ParamKind::Bool, false)];
+		let event = Event { signature: "baz(address,bool)", inputs: &inputs, anonymous: false };
+
+		assert_eq!(event.encode_log(&[Token::Bool(true)]).unwrap_err().kind, ErrorKind::TypeMismatch);
+
+		let error = event.encode_log(&[Token::Address([0u8; 20].into()), Token::Uint(1.into())]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::TypeMismatch);
+		assert_eq!(error.path.segments(), &[PathSegment::Input(1)]);
+	}
+
+	#[test]
+	fn test_decode_as() {
+		let inputs = [
+			Param::new(ParamKind::Address, true),
+			Param::new(ParamKind::String, true),
+			Param::new(ParamKind::Uint(256), false),
+		];
+		let event = Event { signature: "Named(address,string,uint256)", inputs: &inputs, anonymous: false };
+		let from = Address::from([0x11u8; 20]);
+		let (topics, data) =
+			event.encode_log(&[Token::Address(from), Token::String(b"x".to_vec()), Token::Uint(5.into())]).unwrap();
+
+		let decoded = event.decode_as::<(Address, H256, U256)>(topics.clone(), data.clone()).unwrap();
+		assert_eq!(decoded, (from, keccak256("x"), U256::from(5)));
+
+		let error = event.decode_as::<(Address, bool, U256)>(topics, data).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::TypeMismatch);
+	}
+
+	#[test]
+	fn test_registry() {
+		let registry = [
+			"event Transfer(address indexed from, address indexed to, uint256 value)",
+			"event Sent(uint256 nonce) anonymous",
+			"event Paid(address indexed to, bytes memo) anonymous",
+			"event Note(bytes data) anonymous",
+			"event Memo(string text) anonymous",
+		]
+		.iter()
+		.map(|event| event.parse::<OwnedEvent>().unwrap())
+		.collect::<EventRegistry>();
+		assert_eq!(registry.iter().count(), 5);
+
+		let transfer = registry.get(&keccak256("Transfer(address,address,uint256)")).unwrap();
+		let tokens =
+			vec![Token::Address([0x11u8; 20].into()), Token::Address([0x22u8; 20].into()), Token::Uint(5.into())];
+		let (topics, data) = transfer.as_event().encode_log(&tokens).unwrap();
+		let (event, decoded) = registry.decode(topics.clone(), data.clone()).unwrap();
+		assert_eq!((event.signature.as_str(), decoded), ("Transfer(address,address,uint256)", tokens));
+
+		// a known topic is never tried against anonymous events
+		let error = registry.decode(topics[..1].to_vec(), data.clone()).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::TopicCountMismatch);
+
+		// only `Sent` has no topic and a single word of data
+		let (event, decoded) = registry.decode(vec![], data.clone()).unwrap();
+		assert_eq!((event.signature.as_str(), decoded), ("Sent(uint256)", vec![Token::Uint(5.into())]));
+
+		// the single candidate reports its own error
+		let data = encode(&[Token::Uint(64.into()), Token::Uint(0.into())]);
+		let error = registry.decode(topics[2..].to_vec(), data).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::OutOfBounds);
+		assert_eq!(error.path.segments(), &[PathSegment::Input(1)]);
+
+		let data = encode(&[Token::Bytes(b"hello".to_vec())]);
+		assert_eq!(registry.candidates(&[], &data).len(), 2);
+		assert_eq!(registry.decode(vec![], data).unwrap_err().kind, ErrorKind::AmbiguousEvent);
+
+		let error = registry.decode(vec![H256::zero(); 2], vec![]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::SignatureMismatch);
+	}
+
+	#[test]
+	fn test_find_input() {
+		let event: OwnedEvent =
+			"event Filled(address indexed maker, (uint256 amount, (bytes32 id) order) fill, uint8)".parse().unwrap();
+		let order = Token::Tuple(vec![Token::FixedBytes(vec![7u8; 32])]);
+		let tokens = vec![
+			Token::Address([0x11u8; 20].into()),
+			Token::Tuple(vec![Token::Uint(5.into()), order.clone()]),
+			Token::Uint(1.into()),
+		];
+		let event = event.as_event();
+
+		assert_eq!(event.find_input(&tokens, "maker"), Some(&tokens[0]));
+		assert_eq!(event.find_input(&tokens, "fill.amount"), Some(&Token::Uint(5.into())));
+		assert_eq!(event.find_input(&tokens, "fill.order"), Some(&order));
+		assert_eq!(event.find_input(&tokens, "fill.order.id"), Some(&Token::FixedBytes(vec![7u8; 32])));
+		assert_eq!(event.find_input(&tokens, "amount"), None);
+		assert_eq!(event.find_input(&tokens, "maker.amount"), None);
+		assert_eq!(event.find_input(&tokens, ""), None);
+	}
 }
@@ -0,0 +1,259 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Contract function.
+use crate::std::{String, Vec};
+
+use crate::{
+	decoder::decode_shapes,
+	encoder::encode_shapes,
+	param::{find_token, kinds},
+	signature,
+	util::keccak256,
+	DecodeLimits, DecodedParams, Error, ErrorKind, FunctionParam, Token,
+};
+
+/// Whether a function reads or modifies state and accepts ether.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "lowercase"))]
+pub enum StateMutability {
+	/// Does not read or modify state.
+	Pure,
+	/// Reads but does not modify state.
+	View,
+	/// Modifies state, rejects ether.
+	NonPayable,
+	/// Modifies state, accepts ether.
+	Payable,
+}
+
+/// Contract function.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function<'a> {
+	/// Function signature. Like "transfer(address,uint256)".
+	pub signature: &'a str,
+	/// Function input.
+	pub inputs: &'a [FunctionParam],
+	/// Function output.
+	pub outputs: &'a [FunctionParam],
+	/// Function state mutability.
+	pub state_mutability: StateMutability,
+}
+
+impl<'a> Function<'a> {
+	/// Returns the first four bytes of the signature hash, which prefix the calldata.
+	pub fn selector(&self) -> [u8; 4] {
+		let mut selector = [0u8; 4];
+		selector.copy_from_slice(&keccak256(self.signature.as_bytes())[..4]);
+		selector
+	}
+
+	/// Returns the name of the function, see `ethabi_decode::name`.
+	pub fn name(&self) -> &'a str {
+		signature::name(self.signature)
+	}
+
+	/// Checks that `signature` is the canonical signature for `inputs`, see `ethabi_decode::check_signature`.
+	pub fn check_signature(&self) -> Result<(), Error> {
+		signature::check_signature(self.signature, kinds(self.inputs))
+	}
+
+	/// Encodes the calldata for a call with the given input tokens, checking
+	/// them against `inputs` like `encode_params`.
+	pub fn encode_input(&self, tokens: &[Token]) -> Result<Vec<u8>, Error> {
+		let mut calldata = self.selector().to_vec();
+		calldata.extend(encode_shapes(kinds(self.inputs), tokens)?);
+		Ok(calldata)
+	}
+
+	/// Decodes calldata, checking that it starts with the function selector.
+	pub fn decode_input(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+		match data.split_first_chunk::<4>() {
+			Some((selector, params)) if *selector == self.selector() => {
+				decode_shapes(kinds(self.inputs), params, &DecodeLimits::default(), false)
+			}
+			_ => Err(ErrorKind::SelectorMismatch.into()),
+		}
+	}
+
+	/// Encodes the data returned by the function, checking the tokens against
+	/// `outputs` like `encode_params`.
+	pub fn encode_output(&self, tokens: &[Token]) -> Result<Vec<u8>, Error> {
+		encode_shapes(kinds(self.outputs), tokens)
+	}
+
+	/// Decodes the data returned by the function.
+	pub fn decode_output(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+		decode_shapes(kinds(self.outputs), data, &DecodeLimits::default(), false)
+	}
+
+	/// Decodes calldata like `decode_input`, keeping the name and type of each value.
+	pub fn decode_input_named(&self, data: &[u8]) -> Result<DecodedParams, Error> {
+		Ok(DecodedParams::from_function(self.inputs, self.decode_input(data)?))
+	}
+
+	/// Decodes returned data like `decode_output`, keeping the name and type of each value.
+	pub fn decode_output_named(&self, data: &[u8]) -> Result<DecodedParams, Error> {
+		Ok(DecodedParams::from_function(self.outputs, self.decode_output(data)?))
+	}
+
+	/// Returns the input named `name` among tokens decoded by `decode_input`.
+	/// A dotted name like "order.amount" steps into tuple components.
+	pub fn find_input<'t>(&self, tokens: &'t [Token], name: &str) -> Option<&'t Token> {
+		find_token(self.inputs.iter().map(|p| &p.names), tokens, name)
+	}
+
+	/// Returns the output named `name` among tokens decoded by `decode_output`,
+	/// see `find_input`.
+	pub fn find_output<'t>(&self, tokens: &'t [Token], name: &str) -> Option<&'t Token> {
+		find_token(self.outputs.iter().map(|p| &p.names), tokens, name)
+	}
+}
+
+/// Function owning its definition, with the signature derived from its name and inputs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnedFunction {
+	/// Function signature. Like "transfer(address,uint256)".
+	pub signature: String,
+	/// Function input.
+	pub inputs: Vec<FunctionParam>,
+	/// Function output.
+	pub outputs: Vec<FunctionParam>,
+	/// Function state mutability.
+	pub state_mutability: StateMutability,
+}
+
+impl OwnedFunction {
+	/// Creates a function, computing its canonical signature from `name` and `inputs`.
+	pub fn new(
+		name: &str,
+		inputs: Vec<FunctionParam>,
+		outputs: Vec<FunctionParam>,
+		state_mutability: StateMutability,
+	) -> Self {
+		let signature = signature::signature(name, kinds(&inputs));
+		OwnedFunction { signature, inputs, outputs, state_mutability }
+	}
+
+	/// Borrows the function, to encode and decode calls with it.
+	pub fn as_function(&self) -> Function<'_> {
+		Function {
+			signature: &self.signature,
+			inputs: &self.inputs,
+			outputs: &self.outputs,
+			state_mutability: self.state_mutability,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{ErrorKind, Function, FunctionParam, OwnedFunction, ParamKind, PathSegment, StateMutability, Token};
+	use hex_literal::hex;
+
+	const TRANSFER: Function<'static> = Function {
+		signature: "transfer(address,uint256)",
+		inputs: &[FunctionParam::new(ParamKind::Address), FunctionParam::new(ParamKind::Uint(256))],
+		outputs: &[FunctionParam::new(ParamKind::Bool)],
+		state_mutability: StateMutability::NonPayable,
+	};
+
+	#[test]
+	fn test_selector() {
+		assert_eq!(TRANSFER.selector(), hex!("a9059cbb"));
+	}
+
+	#[test]
+	fn test_input_round_trip() {
+		let tokens = vec![Token::Address([0x11u8; 20].into()), Token::Uint(1000.into())];
+		let calldata = TRANSFER.encode_input(&tokens).unwrap();
+
+		assert_eq!(
+			calldata,
+			hex!(
+				"
+				a9059cbb
+				0000000000000000000000001111111111111111111111111111111111111111
+				00000000000000000000000000000000000000000000000000000000000003e8
+			"
+			)
+		);
+		assert_eq!(TRANSFER.decode_input(&calldata).unwrap(), tokens);
+	}
+
+	#[test]
+	fn test_decode_input_selector_mismatch() {
+		let mut calldata =
+			TRANSFER.encode_input(&[Token::Address([0x11u8; 20].into()), Token::Uint(1.into())]).unwrap();
+		calldata[0] = 0;
+
+		assert_eq!(TRANSFER.decode_input(&calldata).unwrap_err().kind, ErrorKind::SelectorMismatch);
+		assert_eq!(TRANSFER.decode_input(&hex!("a9059c")).unwrap_err().kind, ErrorKind::SelectorMismatch);
+	}
+
+	#[test]
+	fn test_output_round_trip() {
+		let encoded = TRANSFER.encode_output(&[Token::Bool(true)]).unwrap();
+
+		assert_eq!(encoded, hex!("0000000000000000000000000000000000000000000000000000000000000001"));
+		assert_eq!(TRANSFER.decode_output(&encoded).unwrap(), vec![Token::Bool(true)]);
+	}
+
+	#[test]
+	fn test_encode_checks_tokens() {
+		let error = TRANSFER.encode_input(&[Token::Uint(1.into()), Token::Uint(1.into())]).unwrap_err();
+		assert_eq!((error.kind, error.path.segments()), (ErrorKind::TypeMismatch, &[PathSegment::Input(0)][..]));
+
+		let error = TRANSFER.encode_input(&[Token::Address([0x11u8; 20].into())]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::TypeMismatch);
+
+		// outputs are checked against `outputs`, not `inputs`
+		let error = TRANSFER.encode_output(&[Token::Uint(1.into())]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::TypeMismatch);
+	}
+
+	#[test]
+	fn test_check_signature() {
+		assert!(TRANSFER.check_signature().is_ok());
+
+		let function = Function { signature: "transfer(address,uint)", ..TRANSFER };
+		assert_eq!(function.check_signature().unwrap_err().path.segments(), &[PathSegment::Input(1)]);
+	}
+
+	#[test]
+	fn test_owned_function() {
+		let function = OwnedFunction::new(
+			"transfer",
+			vec![FunctionParam::new(ParamKind::Address), FunctionParam::new(ParamKind::Uint(256))],
+			vec![FunctionParam::new(ParamKind::Bool)],
+			StateMutability::NonPayable,
+		);
+
+		assert_eq!(function.signature, "transfer(address,uint256)");
+		assert_eq!(function.as_function(), TRANSFER);
+		assert_eq!(function.as_function().selector(), hex!("a9059cbb"));
+	}
+
+	#[test]
+	fn test_find_param() {
+		let function: OwnedFunction =
+			"function swap(address to, (uint128 amount, bool exact) params) returns (uint256 out, uint256)"
+				.parse()
+				.unwrap();
+		let function = function.as_function();
+		let inputs =
+			[Token::Address([0x11u8; 20].into()), Token::Tuple(vec![Token::Uint(5.into()), Token::Bool(true)])];
+		let outputs = [Token::Uint(6.into()), Token::Uint(7.into())];
+
+		assert_eq!(function.find_input(&inputs, "to"), Some(&inputs[0]));
+		assert_eq!(function.find_input(&inputs, "params.exact"), Some(&Token::Bool(true)));
+		assert_eq!(function.find_input(&inputs, "out"), None);
+		assert_eq!(function.find_output(&outputs, "out"), Some(&outputs[0]));
+		assert_eq!(TRANSFER.find_input(&inputs, "to"), None);
+	}
+}
@@ -0,0 +1,395 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Loading of the JSON ABI emitted by solc, enabled by the `serde` feature.
+use core::fmt::Write;
+
+use serde::{de, Deserialize, Deserializer};
+
+use crate::std::{Box, String, Vec};
+use crate::{
+	Error, FunctionParam, OwnedAbiError, OwnedEvent, OwnedFunction, Param, ParamKind, ParamNames, StateMutability,
+};
+
+/// Entry of a JSON ABI, which is an array of these.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AbiItem {
+	/// Contract function.
+	Function(OwnedFunction),
+	/// Contract event.
+	Event(OwnedEvent),
+	/// Custom error.
+	Error(OwnedAbiError),
+	/// Contract constructor.
+	Constructor {
+		/// Constructor params.
+		#[serde(default)]
+		inputs: Vec<FunctionParam>,
+		/// Constructor state mutability.
+		#[serde(flatten)]
+		state_mutability: JsonStateMutability,
+	},
+	/// Function called when no other function matches.
+	Fallback {
+		/// Fallback state mutability.
+		#[serde(flatten)]
+		state_mutability: JsonStateMutability,
+	},
+	/// Function called on plain ether transfers.
+	Receive {
+		/// Receive state mutability.
+		#[serde(flatten)]
+		state_mutability: JsonStateMutability,
+	},
+}
+
+/// State mutability, also understanding the `constant` and `payable` flags of
+/// ABIs emitted before solc 0.4.16.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(from = "JsonMutability")]
+pub struct JsonStateMutability(pub StateMutability);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonMutability {
+	state_mutability: Option<StateMutability>,
+	#[serde(default)]
+	constant: bool,
+	#[serde(default)]
+	payable: bool,
+}
+
+impl From<JsonMutability> for JsonStateMutability {
+	fn from(json: JsonMutability) -> Self {
+		JsonStateMutability(match json.state_mutability {
+			Some(state_mutability) => state_mutability,
+			None if json.payable => StateMutability::Payable,
+			None if json.constant => StateMutability::View,
+			None => StateMutability::NonPayable,
+		})
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonParam {
+	#[serde(default)]
+	name: String,
+	#[serde(rename = "type")]
+	kind: String,
+	internal_type: Option<String>,
+	#[serde(default)]
+	components: Vec<JsonParam>,
+	#[serde(default)]
+	indexed: bool,
+}
+
+impl JsonParam {
+	fn param_kind(&self) -> Result<ParamKind, Error> {
+		match self.kind.strip_prefix("tuple") {
+			Some(dimensions) => {
+				let components =
+					self.components.iter().map(|c| c.param_kind().map(Box::new)).collect::<Result<_, _>>()?;
+				// let the type parser handle the array dimensions following `tuple`
+				let mut kind = String::new();
+				let _ = write!(kind, "{}{}", ParamKind::Tuple(components), dimensions);
+				kind.parse()
+			}
+			None => self.kind.parse(),
+		}
+	}
+
+	/// Returns the names of the param, solc giving unnamed params an empty name.
+	fn names(&self) -> ParamNames {
+		ParamNames {
+			name: Some(self.name.clone()).filter(|name| !name.is_empty()),
+			internal_type: self.internal_type.clone(),
+			components: self.components.iter().map(JsonParam::names).collect(),
+		}
+	}
+}
+
+/// Deserializes a JSON ABI param, like `{ "type": "tuple[]", "components": [...] }`.
+impl<'de> Deserialize<'de> for ParamKind {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		JsonParam::deserialize(deserializer)?.param_kind().map_err(de::Error::custom)
+	}
+}
+
+/// Deserializes a JSON ABI event param, like `{ "type": "address", "indexed": true }`.
+impl<'de> Deserialize<'de> for Param {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let json = JsonParam::deserialize(deserializer)?;
+		let kind = json.param_kind().map_err(de::Error::custom)?;
+		Ok(Param { kind, indexed: json.indexed, names: json.names() })
+	}
+}
+
+/// Deserializes a JSON ABI function or error param, like `{ "name": "to", "type": "address" }`.
+impl<'de> Deserialize<'de> for FunctionParam {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let json = JsonParam::deserialize(deserializer)?;
+		let kind = json.param_kind().map_err(de::Error::custom)?;
+		Ok(FunctionParam { kind, names: json.names() })
+	}
+}
+
+#[derive(Deserialize)]
+struct JsonEvent {
+	name: String,
+	#[serde(default)]
+	inputs: Vec<Param>,
+	#[serde(default)]
+	anonymous: bool,
+}
+
+/// Deserializes a JSON ABI event entry, computing its signature.
+impl<'de> Deserialize<'de> for OwnedEvent {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let json = JsonEvent::deserialize(deserializer)?;
+		Ok(OwnedEvent::new(&json.name, json.inputs, json.anonymous))
+	}
+}
+
+#[derive(Deserialize)]
+struct JsonFunction {
+	name: String,
+	#[serde(default)]
+	inputs: Vec<FunctionParam>,
+	#[serde(default)]
+	outputs: Vec<FunctionParam>,
+	#[serde(flatten)]
+	state_mutability: JsonStateMutability,
+}
+
+/// Deserializes a JSON ABI function entry, computing its signature.
+impl<'de> Deserialize<'de> for OwnedFunction {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let json = JsonFunction::deserialize(deserializer)?;
+		Ok(OwnedFunction::new(&json.name, json.inputs, json.outputs, json.state_mutability.0))
+	}
+}
+
+#[derive(Deserialize)]
+struct JsonAbiError {
+	name: String,
+	#[serde(default)]
+	inputs: Vec<FunctionParam>,
+}
+
+/// Deserializes a JSON ABI error entry, computing its signature.
+impl<'de> Deserialize<'de> for OwnedAbiError {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let json = JsonAbiError::deserialize(deserializer)?;
+		Ok(OwnedAbiError::new(&json.name, json.inputs))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{AbiItem, JsonStateMutability};
+	use crate::{
+		Contract, FunctionParam, OwnedAbiError, OwnedEvent, OwnedFunction, Param, ParamKind, ParamNames,
+		StateMutability,
+	};
+	use hex_literal::hex;
+
+	const ABI: &str = r#"[
+		{
+			"type": "constructor",
+			"inputs": [{ "name": "owner", "type": "address", "internalType": "address" }],
+			"stateMutability": "nonpayable"
+		},
+		{
+			"type": "function",
+			"name": "submit",
+			"inputs": [
+				{
+					"name": "messages",
+					"type": "tuple[]",
+					"internalType": "struct Message[]",
+					"components": [
+						{ "name": "target", "type": "address", "internalType": "address" },
+						{ "name": "nonce", "type": "uint64", "internalType": "uint64" },
+						{ "name": "payload", "type": "bytes", "internalType": "bytes" }
+					]
+				},
+				{ "name": "proof", "type": "bytes32[2]", "internalType": "bytes32[2]" }
+			],
+			"outputs": [{ "name": "", "type": "bool", "internalType": "bool" }],
+			"stateMutability": "payable"
+		},
+		{
+			"type": "event",
+			"name": "Transfer",
+			"inputs": [
+				{ "name": "from", "type": "address", "indexed": true, "internalType": "address" },
+				{ "name": "to", "type": "address", "indexed": true, "internalType": "address" },
+				{ "name": "value", "type": "uint256", "indexed": false, "internalType": "uint256" }
+			],
+			"anonymous": false
+		},
+		{
+			"type": "error",
+			"name": "InsufficientBalance",
+			"inputs": [
+				{ "name": "available", "type": "uint256", "internalType": "uint256" },
+				{ "name": "required", "type": "uint256", "internalType": "uint256" }
+			]
+		},
+		{ "type": "fallback", "stateMutability": "payable" },
+		{ "type": "receive", "stateMutability": "payable" }
+	]"#;
+
+	#[test]
+	fn test_load_abi() {
+		let mut items: Vec<AbiItem> = serde_json::from_str(ABI).unwrap();
+		// names are checked by `test_param_names`
+		for item in &mut items {
+			let names = match item {
+				AbiItem::Function(function) => {
+					function.inputs.iter_mut().chain(&mut function.outputs).map(|p| &mut p.names).collect()
+				}
+				AbiItem::Event(event) => event.inputs.iter_mut().map(|p| &mut p.names).collect(),
+				AbiItem::Error(error) => error.inputs.iter_mut().map(|p| &mut p.names).collect(),
+				AbiItem::Constructor { inputs, .. } => inputs.iter_mut().map(|p| &mut p.names).collect(),
+				_ => Vec::new(),
+			};
+			names.into_iter().for_each(|names| *names = ParamNames::default());
+		}
+		let message = ParamKind::Tuple(vec![
+			Box::new(ParamKind::Address),
+			Box::new(ParamKind::Uint(64)),
+			Box::new(ParamKind::Bytes),
+		]);
+
+		assert_eq!(
+			items,
+			vec![
+				AbiItem::Constructor {
+					inputs: vec![FunctionParam::new(ParamKind::Address)],
+					state_mutability: JsonStateMutability(StateMutability::NonPayable),
+				},
+				AbiItem::Function(OwnedFunction::new(
+					"submit",
+					vec![
+						FunctionParam::new(ParamKind::Array(Box::new(message))),
+						FunctionParam::new(ParamKind::FixedArray(Box::new(ParamKind::FixedBytes(32)), 2)),
+					],
+					vec![FunctionParam::new(ParamKind::Bool)],
+					StateMutability::Payable,
+				)),
+				AbiItem::Event(OwnedEvent::new(
+					"Transfer",
+					vec![
+						Param::new(ParamKind::Address, true),
+						Param::new(ParamKind::Address, true),
+						Param::new(ParamKind::Uint(256), false),
+					],
+					false,
+				)),
+				AbiItem::Error(OwnedAbiError::new(
+					"InsufficientBalance",
+					vec![FunctionParam::new(ParamKind::Uint(256)), FunctionParam::new(ParamKind::Uint(256))]
+				)),
+				AbiItem::Fallback { state_mutability: JsonStateMutability(StateMutability::Payable) },
+				AbiItem::Receive { state_mutability: JsonStateMutability(StateMutability::Payable) },
+			]
+		);
+
+		match &items[1] {
+			AbiItem::Function(function) => {
+				assert_eq!(function.signature, "submit((address,uint64,bytes)[],bytes32[2])")
+			}
+			_ => unreachable!(),
+		}
+	}
+
+	#[test]
+	fn test_param_names() {
+		let items: Vec<AbiItem> = serde_json::from_str(ABI).unwrap();
+
+		let owner = match &items[0] {
+			AbiItem::Constructor { inputs, .. } => &inputs[0],
+			_ => unreachable!(),
+		};
+		assert_eq!(owner.name(), Some("owner"));
+		assert_eq!(owner.names.internal_type.as_deref(), Some("address"));
+
+		let function = match &items[1] {
+			AbiItem::Function(function) => function,
+			_ => unreachable!(),
+		};
+		let messages = &function.inputs[0].names;
+		assert_eq!(messages.name.as_deref(), Some("messages"));
+		assert_eq!(messages.struct_name(), Some("Message"));
+		let names = messages.components.iter().map(|c| c.name.as_deref().unwrap()).collect::<Vec<_>>();
+		assert_eq!(names, ["target", "nonce", "payload"]);
+		assert_eq!(messages.components[1].internal_type.as_deref(), Some("uint64"));
+		// solc names unnamed outputs ""
+		assert_eq!(function.outputs[0].name(), None);
+
+		let event = match &items[2] {
+			AbiItem::Event(event) => event,
+			_ => unreachable!(),
+		};
+		assert_eq!(event.inputs.iter().map(|p| p.name().unwrap()).collect::<Vec<_>>(), ["from", "to", "value"]);
+
+		let error = match &items[3] {
+			AbiItem::Error(error) => error,
+			_ => unreachable!(),
+		};
+		assert_eq!(error.inputs.iter().map(|p| p.name().unwrap()).collect::<Vec<_>>(), ["available", "required"]);
+	}
+
+	#[test]
+	fn test_load_contract() {
+		let contract: Contract = serde_json::from_str::<Vec<AbiItem>>(ABI).unwrap().into_iter().collect();
+
+		assert_eq!(contract.constructor().unwrap().inputs[0].name(), Some("owner"));
+		assert_eq!(contract.functions().count(), 1);
+		assert_eq!(contract.events().count(), 1);
+		assert_eq!(contract.error(hex!("cf479181")).unwrap().signature, "InsufficientBalance(uint256,uint256)");
+	}
+
+	#[test]
+	fn test_legacy_state_mutability() {
+		let function: OwnedFunction = serde_json::from_str(
+			r#"{ "type": "function", "name": "get", "inputs": [], "outputs": [], "constant": true, "payable": false }"#,
+		)
+		.unwrap();
+		assert_eq!(function.state_mutability, StateMutability::View);
+
+		let function: OwnedFunction =
+			serde_json::from_str(r#"{ "type": "function", "name": "pay", "payable": true }"#).unwrap();
+		assert_eq!(function.state_mutability, StateMutability::Payable);
+	}
+
+	#[test]
+	fn test_nested_tuple_components() {
+		let kind: ParamKind = serde_json::from_str(
+			r#"{
+				"type": "tuple[2][]",
+				"components": [
+					{ "type": "tuple", "components": [{ "type": "bool" }, { "type": "string" }] },
+					{ "type": "int8" }
+				]
+			}"#,
+		)
+		.unwrap();
+
+		assert_eq!(kind.to_string(), "((bool,string),int8)[2][]");
+	}
+
+	#[test]
+	fn test_invalid_type() {
+		assert!(serde_json::from_str::<ParamKind>(r#"{ "type": "uint7" }"#).is_err());
+		assert!(serde_json::from_str::<ParamKind>(r#"{ "type": "tuple[x]", "components": [] }"#).is_err());
+	}
+}
@@ -11,29 +11,56 @@
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
+mod abi_error;
+mod contract;
+mod decoded;
 mod decoder;
 mod encoder;
+mod error;
 mod event;
+mod function;
+#[cfg(feature = "serde")]
+mod json;
+mod packed;
 mod param;
+mod parser;
+mod revert;
+mod schema;
+mod signature;
 mod std;
 mod token;
+mod tokenizable;
 mod util;
 
 pub use crate::{
-	decoder::decode,
-	encoder::{encode, encode_function},
-	event::Event,
-	param::{Param, ParamKind},
-	token::Token,
+	abi_error::{AbiError, ErrorRegistry, OwnedAbiError},
+	contract::{Constructor, Contract},
+	decoded::{DecodedLog, DecodedParam, DecodedParams},
+	decoder::{
+		decode, decode_borrowed, decode_borrowed_with_limits, decode_strict, decode_strict_with_limits,
+		decode_with_limits, DecodeLimits, LazyDecoder,
+	},
+	encoder::{encode, encode_function, encode_params},
+	error::{Error, ErrorKind, ParamPath, PathSegment},
+	event::{EthEvent, Event, EventRegistry, OwnedEvent},
+	function::{Function, OwnedFunction, StateMutability},
+	packed::{encode_packed, keccak256_packed},
+	param::{FunctionParam, Param, ParamKind, ParamNames},
+	revert::{decode_revert, PanicCode, Revert, ERROR_SELECTOR, PANIC_SELECTOR},
+	schema::{StaticEvent, StaticFunction, StaticFunctionParam, StaticParam, StaticParamKind, StaticParamNames},
+	signature::{check_signature, name, selector, signature, signature_hash},
+	token::{Token, TokenRef},
+	tokenizable::{decode_as, Tokenizable},
 };
 
-#[derive(Debug)]
-pub enum Error {
-	/// Invalid entity such as a bad function name.
-	InvalidName,
-	/// Invalid data.
-	InvalidData
-}
+#[cfg(feature = "serde")]
+pub use crate::json::{AbiItem, JsonStateMutability};
+
+#[cfg(feature = "derive")]
+pub use ethabi_decode_derive::{event, function, EthAbiType, EthEvent};
+
+#[doc(hidden)]
+pub use crate::tokenizable::derive as __private;
 
 /// ABI Address
 pub use ethereum_types::Address;
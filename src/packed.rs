@@ -0,0 +1,204 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Non-standard packed encoding, as produced by Solidity's `abi.encodePacked`.
+use crate::std::Vec;
+use crate::{encode, encoder::conform, util::keccak256, Error, ErrorKind, ParamKind, PathSegment, Token, Word, H256};
+
+/// Appends the packed encoding of `token`, already conformed to `kind`, to `out`.
+///
+/// Array elements keep their standard 32 byte encoding, so only elementary
+/// static types are allowed inside arrays. Tuples cannot be packed.
+fn encode_token(kind: &ParamKind, token: &Token, out: &mut Vec<u8>) -> Result<(), ErrorKind> {
+	match (kind, token) {
+		(ParamKind::Address, Token::Address(address)) => out.extend_from_slice(address.as_bytes()),
+		(ParamKind::Bool, Token::Bool(b)) => out.push(*b as u8),
+		(ParamKind::Uint(size), Token::Uint(value)) | (ParamKind::Int(size), Token::Int(value)) => {
+			// the value fits, so the bytes left out are only its zero or sign extension
+			let word: Word = (*value).into();
+			out.extend_from_slice(&word[32 - size / 8..]);
+		}
+		// the token fits its type, but `bytesN` only goes up to `bytes32`
+		(ParamKind::FixedBytes(size), Token::FixedBytes(_)) if *size > 32 => return Err(ErrorKind::ValueOutOfRange),
+		(ParamKind::FixedBytes(size), Token::FixedBytes(bytes)) => {
+			out.extend_from_slice(bytes);
+			out.resize(out.len() + size - bytes.len(), 0);
+		}
+		(ParamKind::Bytes, Token::Bytes(bytes)) | (ParamKind::String, Token::String(bytes)) => {
+			out.extend_from_slice(bytes)
+		}
+		(ParamKind::Array(element), Token::Array(tokens))
+		| (ParamKind::FixedArray(element, _), Token::FixedArray(tokens)) => {
+			if !matches!(
+				**element,
+				ParamKind::Address
+					| ParamKind::Bool
+					| ParamKind::Uint(_)
+					| ParamKind::Int(_)
+					| ParamKind::FixedBytes(_)
+			) {
+				return Err(ErrorKind::InvalidType);
+			}
+			if matches!(**element, ParamKind::FixedBytes(size) if size > 32) {
+				return Err(ErrorKind::ValueOutOfRange);
+			}
+			out.extend(encode(tokens));
+		}
+		_ => return Err(ErrorKind::InvalidType),
+	}
+	Ok(())
+}
+
+/// Encodes tokens like Solidity's `abi.encodePacked`.
+///
+/// Every value takes the minimal width of its param type, `bytes` and `string`
+/// are not length-prefixed, and array elements are padded to 32 bytes. Tokens
+/// are checked as by `encode_params`, so this fails with `ErrorKind::TypeMismatch`
+/// if a token does not match its param type and `ErrorKind::ValueOutOfRange` if
+/// it does not fit the width of the type or is a `bytesN` wider than 32 bytes.
+/// Fails with `ErrorKind::InvalidType` for tuples and nested or dynamic array
+/// elements, which Solidity does not pack.
+pub fn encode_packed(params: &[ParamKind], tokens: &[Token]) -> Result<Vec<u8>, Error> {
+	if params.len() != tokens.len() {
+		return Err(ErrorKind::TypeMismatch.into());
+	}
+
+	let mut out = Vec::new();
+	for (i, (kind, token)) in params.iter().zip(tokens).enumerate() {
+		let token = conform(kind, token).map_err(|e| e.within(PathSegment::Input(i)))?;
+		encode_token(kind, &token, &mut out).map_err(|e| Error::new(e).within(PathSegment::Input(i)))?;
+	}
+	Ok(out)
+}
+
+/// Returns `keccak256(abi.encodePacked(..))`, see `encode_packed`.
+pub fn keccak256_packed(params: &[ParamKind], tokens: &[Token]) -> Result<H256, Error> {
+	encode_packed(params, tokens).map(|packed| keccak256(&packed).into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{encode_packed, keccak256_packed};
+	use crate::{util::keccak256, ErrorKind, ParamKind, PathSegment, Token, H256, U256};
+	use hex_literal::hex;
+
+	#[test]
+	fn test_elementary() {
+		let params = [
+			ParamKind::Int(16),
+			ParamKind::FixedBytes(1),
+			ParamKind::Uint(16),
+			ParamKind::String,
+			ParamKind::Address,
+			ParamKind::Bool,
+		];
+		let tokens = [
+			Token::Int(U256::MAX),
+			Token::FixedBytes(vec![0x42]),
+			Token::Uint(0x03.into()),
+			Token::String(b"Hello, world!".to_vec()),
+			Token::Address([0x11u8; 20].into()),
+			Token::Bool(true),
+		];
+
+		// example from the Solidity docs, extended with an address and a bool
+		assert_eq!(
+			encode_packed(&params, &tokens).unwrap(),
+			hex!("ffff42000348656c6c6f2c20776f726c6421 1111111111111111111111111111111111111111 01")
+		);
+	}
+
+	#[test]
+	fn test_arrays_pad_elements() {
+		let params =
+			[ParamKind::Array(Box::new(ParamKind::Uint(8))), ParamKind::FixedArray(Box::new(ParamKind::Bool), 2)];
+		let tokens = [
+			Token::Array(vec![Token::Uint(1.into()), Token::Uint(2.into())]),
+			Token::FixedArray(vec![Token::Bool(true), Token::Bool(false)]),
+		];
+
+		assert_eq!(
+			encode_packed(&params, &tokens).unwrap(),
+			hex!(
+				"
+				0000000000000000000000000000000000000000000000000000000000000001
+				0000000000000000000000000000000000000000000000000000000000000002
+				0000000000000000000000000000000000000000000000000000000000000001
+				0000000000000000000000000000000000000000000000000000000000000000
+			"
+			)
+		);
+	}
+
+	#[test]
+	fn test_fixed_bytes_padding() {
+		let packed = encode_packed(&[ParamKind::FixedBytes(4)], &[Token::FixedBytes(vec![0xab, 0xcd])]).unwrap();
+		assert_eq!(packed, hex!("abcd0000"));
+	}
+
+	#[test]
+	fn test_keccak256_packed() {
+		let params = [ParamKind::Bytes, ParamKind::Uint(64)];
+		let tokens = [Token::Bytes(b"abc".to_vec()), Token::Uint(1.into())];
+
+		assert_eq!(
+			keccak256_packed(&params, &tokens).unwrap(),
+			H256::from(keccak256(&hex!("616263 0000000000000001")))
+		);
+	}
+
+	#[test]
+	fn test_errors() {
+		let error =
+			encode_packed(&[ParamKind::Bool, ParamKind::Uint(8)], &[Token::Bool(true), Token::Uint(256.into())])
+				.unwrap_err();
+		assert_eq!(error.kind, ErrorKind::ValueOutOfRange);
+		assert_eq!(error.path.segments(), &[PathSegment::Input(1)]);
+
+		// -129 does not fit in an int8, -128 does
+		let minus = |n: u64| Token::Int(U256::zero().overflowing_sub(n.into()).0);
+		assert!(encode_packed(&[ParamKind::Int(8)], &[minus(128)]).is_ok());
+		let error = encode_packed(&[ParamKind::Int(8)], &[minus(129)]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::ValueOutOfRange);
+
+		let error = encode_packed(&[ParamKind::Address], &[Token::Bool(true)]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::TypeMismatch);
+		assert_eq!(encode_packed(&[], &[Token::Bool(true)]).unwrap_err().kind, ErrorKind::TypeMismatch);
+
+		let array = ParamKind::Array(Box::new(ParamKind::Uint(8)));
+		let error =
+			encode_packed(&[array], &[Token::Array(vec![Token::Uint(1.into()), Token::Uint(256.into())])]).unwrap_err();
+		assert_eq!(error.path.segments(), &[PathSegment::Input(0), PathSegment::Element(1)]);
+
+		let nested = ParamKind::Array(Box::new(ParamKind::Array(Box::new(ParamKind::Bool))));
+		let error = encode_packed(&[nested], &[Token::Array(vec![])]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::InvalidType);
+
+		let tuple = ParamKind::Tuple(vec![Box::new(ParamKind::Bool)]);
+		let error = encode_packed(&[tuple], &[Token::Tuple(vec![Token::Bool(true)])]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::InvalidType);
+	}
+
+	#[test]
+	fn test_fixed_bytes_longer_than_32() {
+		let bytes = Token::FixedBytes(vec![0xab; 33]);
+		let error = encode_packed(&[ParamKind::Bool, ParamKind::FixedBytes(33)], &[Token::Bool(true), bytes.clone()])
+			.unwrap_err();
+		assert_eq!(error.kind, ErrorKind::ValueOutOfRange);
+		assert_eq!(error.path.segments(), &[PathSegment::Input(1)]);
+
+		let array = ParamKind::Array(Box::new(ParamKind::FixedBytes(33)));
+		let error = encode_packed(&[array], &[Token::Array(vec![bytes])]).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::ValueOutOfRange);
+
+		assert_eq!(
+			encode_packed(&[ParamKind::FixedBytes(32)], &[Token::FixedBytes(vec![0xab; 32])]).unwrap(),
+			[0xab; 32]
+		);
+	}
+}
@@ -7,7 +7,14 @@
 // http://www.apache.org/licenses/LICENSE-2.0>. This file may not be 
 // copied, modified, or distributed except according to those terms.
 
-use crate::std::{Box, Vec};
+use core::fmt;
+use ethabi_decode_syntax::{write_type, Shape, TypeShape};
+
+use crate::{DecodeLimits, Error, ErrorKind, PathSegment, Token};
+
+use crate::std::{Box, String, Vec};
+
+pub(crate) use crate::parser::is_valid_int_width;
 
 
 /// Event param specification.
@@ -17,6 +24,106 @@ pub struct Param {
 	pub kind: ParamKind,
 	/// Indexed flag. If true, param is used to build block bloom.
 	pub indexed: bool,
+	/// Names of the param and of its tuple components.
+	pub names: ParamNames,
+}
+
+impl Param {
+	/// Creates a param without a name.
+	pub fn new(kind: ParamKind, indexed: bool) -> Self {
+		Param { kind, indexed, names: ParamNames::default() }
+	}
+
+	/// Creates a param with the given name.
+	pub fn named(name: &str, kind: ParamKind, indexed: bool) -> Self {
+		Param { kind, indexed, names: ParamNames::named(name) }
+	}
+
+	/// Returns the name of the param, if it has one.
+	pub fn name(&self) -> Option<&str> {
+		self.names.name.as_deref()
+	}
+}
+
+/// Function, constructor and custom error param specification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionParam {
+	/// Param type.
+	pub kind: ParamKind,
+	/// Names of the param and of its tuple components.
+	pub names: ParamNames,
+}
+
+impl FunctionParam {
+	/// Creates a param without a name.
+	pub const fn new(kind: ParamKind) -> Self {
+		FunctionParam { kind, names: ParamNames { name: None, internal_type: None, components: Vec::new() } }
+	}
+
+	/// Creates a param with the given name.
+	pub fn named(name: &str, kind: ParamKind) -> Self {
+		FunctionParam { kind, names: ParamNames::named(name) }
+	}
+
+	/// Returns the name of the param, if it has one.
+	pub fn name(&self) -> Option<&str> {
+		self.names.name.as_deref()
+	}
+}
+
+/// Returns the types of function params, for the encoder and decoder.
+pub(crate) fn kinds(params: &[FunctionParam]) -> impl ExactSizeIterator<Item = &ParamKind> + Clone {
+	params.iter().map(|param| &param.kind)
+}
+
+/// Names of a param and of its tuple components, as given by a human-readable
+/// signature or a JSON ABI. They play no part in signatures or encoding.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParamNames {
+	/// Param name. Like "amount".
+	pub name: Option<String>,
+	/// Type as declared in Solidity, the JSON ABI `internalType`. Like "struct Order[]".
+	pub internal_type: Option<String>,
+	/// Names of the components of a tuple, or of the tuples of an array. Empty if unknown.
+	pub components: Vec<ParamNames>,
+}
+
+impl ParamNames {
+	/// Creates the names of a param named `name`, without component names.
+	pub fn named(name: &str) -> Self {
+		ParamNames { name: Some(String::from(name)), ..Self::default() }
+	}
+
+	/// Returns whether neither the param nor any of its components has a name or internal type.
+	pub fn is_empty(&self) -> bool {
+		self.name.is_none() && self.internal_type.is_none() && self.components.iter().all(ParamNames::is_empty)
+	}
+
+	/// Returns the name of the struct the param was declared as, or an array of,
+	/// taken from `internal_type`. Like "Order" for "struct Order[]", or
+	/// "Exchange.Order" for a struct declared in a contract.
+	pub fn struct_name(&self) -> Option<&str> {
+		let internal_type = self.internal_type.as_deref()?.strip_prefix("struct ")?;
+		Some(internal_type.find('[').map_or(internal_type, |end| &internal_type[..end]))
+	}
+}
+
+/// Returns the value named `name` among `tokens`, decoded for params with the
+/// given `names`. A dotted name like "order.amount" steps into tuple components.
+pub(crate) fn find_token<'t, 'n, I>(names: I, tokens: &'t [Token], name: &str) -> Option<&'t Token>
+where
+	I: IntoIterator<Item = &'n ParamNames>,
+{
+	let (first, rest) = match name.split_once('.') {
+		Some((first, rest)) => (first, Some(rest)),
+		None => (name, None),
+	};
+	let (names, token) = names.into_iter().zip(tokens).find(|(names, _)| names.name.as_deref() == Some(first))?;
+	match (rest, token) {
+		(None, token) => Some(token),
+		(Some(rest), Token::Tuple(components)) => find_token(&names.components, components, rest),
+		(Some(_), _) => None,
+	}
 }
 
 /// Function and event param types.
@@ -45,31 +152,165 @@ pub enum ParamKind {
 }
 
 impl ParamKind {
-	/// returns whether a zero length byte slice (`0x`) is
-	/// a valid encoded form of this param type
-	pub fn is_empty_bytes_valid_encoding(&self) -> bool {
+	/// Creates a `Uint(bits)`, failing with `ErrorKind::InvalidType` unless
+	/// `bits` is a multiple of 8 from 8 to 256.
+	pub fn uint(bits: usize) -> Result<Self, Error> {
+		match is_valid_int_width(bits) {
+			true => Ok(ParamKind::Uint(bits)),
+			false => Err(ErrorKind::InvalidType.into()),
+		}
+	}
+
+	/// Creates an `Int(bits)`, failing with `ErrorKind::InvalidType` unless
+	/// `bits` is a multiple of 8 from 8 to 256.
+	pub fn int(bits: usize) -> Result<Self, Error> {
+		match is_valid_int_width(bits) {
+			true => Ok(ParamKind::Int(bits)),
+			false => Err(ErrorKind::InvalidType.into()),
+		}
+	}
+
+	/// Checks the constraints Solidity puts on types, so mistakes in a hand-written
+	/// schema surface before anything is decoded with it.
+	///
+	/// Fails with `ErrorKind::InvalidType` for `Int` and `Uint` widths other than
+	/// a multiple of 8 from 8 to 256, `FixedBytes` lengths outside 1 to 32, fixed
+	/// arrays of length 0 and empty tuples, and with `ErrorKind::LimitExceeded` for
+	/// arrays and tuples nested deeper than the default `DecodeLimits` allow.
+	/// Errors inside tuples are located at the offending component.
+	pub fn validate(&self) -> Result<(), Error> {
+		self.validate_nested(DecodeLimits::default().max_depth)
+	}
+
+	fn validate_nested(&self, depth: usize) -> Result<(), Error> {
 		match self {
-			ParamKind::FixedBytes(len) => *len == 0,
-			ParamKind::FixedArray(_, len) => *len == 0,
-			_ => false,
+			ParamKind::Int(bits) | ParamKind::Uint(bits) if !is_valid_int_width(*bits) => {
+				Err(ErrorKind::InvalidType.into())
+			}
+			ParamKind::FixedBytes(len) if *len == 0 || *len > 32 => Err(ErrorKind::InvalidType.into()),
+			ParamKind::FixedArray(_, 0) => Err(ErrorKind::InvalidType.into()),
+			ParamKind::Tuple(components) if components.is_empty() => Err(ErrorKind::InvalidType.into()),
+			ParamKind::Array(_) | ParamKind::FixedArray(..) | ParamKind::Tuple(_) if depth == 0 => {
+				Err(ErrorKind::LimitExceeded.into())
+			}
+			ParamKind::Array(element) | ParamKind::FixedArray(element, _) => element.validate_nested(depth - 1),
+			ParamKind::Tuple(components) => components.iter().enumerate().try_for_each(|(i, component)| {
+				component.validate_nested(depth - 1).map_err(|e| e.within(PathSegment::Component(i)))
+			}),
+			_ => Ok(()),
 		}
 	}
 
+	/// returns whether a zero length byte slice (`0x`) is
+	/// a valid encoded form of this param type, which only holds for the
+	/// zero-sized types `validate` rejects
+	pub fn is_empty_bytes_valid_encoding(&self) -> bool {
+		is_empty_bytes_valid_encoding(self)
+	}
+
 	/// returns whether a ParamKind is dynamic
 	/// used to decide how the ParamKind should be encoded
 	pub fn is_dynamic(&self) -> bool {
+		is_dynamic(self)
+	}
+}
+
+/// Same as `ParamKind::is_empty_bytes_valid_encoding`, for any `TypeShape`.
+pub(crate) fn is_empty_bytes_valid_encoding<K: TypeShape>(kind: &K) -> bool {
+	matches!(kind.shape(), Shape::FixedBytes(0) | Shape::FixedArray(_, 0))
+}
+
+/// Same as `ParamKind::is_dynamic`, for any `TypeShape`.
+pub(crate) fn is_dynamic<K: TypeShape>(kind: &K) -> bool {
+	match kind.shape() {
+		Shape::Bytes | Shape::String | Shape::Array(_) => true,
+		Shape::FixedArray(element, _) => is_dynamic(element),
+		Shape::Tuple(components) => components.iter().any(|component| is_dynamic(component.as_ref())),
+		_ => false,
+	}
+}
+
+impl TypeShape for ParamKind {
+	type Component = Box<ParamKind>;
+
+	fn shape(&self) -> Shape<'_, Self, Box<ParamKind>> {
 		match self {
-			ParamKind::Bytes | ParamKind::String | ParamKind::Array(_) => true,
-			ParamKind::FixedArray(elem_type, _) => elem_type.is_dynamic(),
-			ParamKind::Tuple(params) => params.iter().any(|param| param.is_dynamic()),
-			_ => false,
+			ParamKind::Address => Shape::Address,
+			ParamKind::Bytes => Shape::Bytes,
+			ParamKind::Int(size) => Shape::Int(*size),
+			ParamKind::Uint(size) => Shape::Uint(*size),
+			ParamKind::Bool => Shape::Bool,
+			ParamKind::String => Shape::String,
+			ParamKind::Array(kind) => Shape::Array(kind),
+			ParamKind::FixedBytes(size) => Shape::FixedBytes(*size),
+			ParamKind::FixedArray(kind, size) => Shape::FixedArray(kind, *size),
+			ParamKind::Tuple(kinds) => Shape::Tuple(kinds),
 		}
 	}
 }
 
+/// Renders the canonical Solidity type, as used in signatures. Like `uint256` or `(address,bytes32)[3]`.
+impl fmt::Display for ParamKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write_type(f, self)
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use crate::ParamKind;
+	use crate::{ErrorKind, ParamKind, ParamNames, PathSegment};
+
+	#[test]
+	fn test_validate() {
+		let tuple = |kinds: Vec<ParamKind>| ParamKind::Tuple(kinds.into_iter().map(Box::new).collect());
+
+		assert_eq!(ParamKind::Uint(8).validate(), Ok(()));
+		assert_eq!(ParamKind::FixedBytes(32).validate(), Ok(()));
+		assert_eq!(tuple(vec![ParamKind::Address, ParamKind::Array(Box::new(ParamKind::Int(256)))]).validate(), Ok(()));
+
+		for kind in [
+			ParamKind::Uint(7),
+			ParamKind::Int(0),
+			ParamKind::FixedBytes(0),
+			ParamKind::FixedBytes(64),
+			ParamKind::FixedArray(Box::new(ParamKind::Bool), 0),
+			tuple(vec![]),
+			ParamKind::Array(Box::new(ParamKind::Uint(300))),
+		] {
+			assert_eq!(kind.validate().unwrap_err().kind, ErrorKind::InvalidType, "{}", kind);
+		}
+
+		let error = tuple(vec![ParamKind::Bool, ParamKind::FixedBytes(33)]).validate().unwrap_err();
+		assert_eq!(error.path.segments(), &[PathSegment::Component(1)]);
+
+		let nested = |depth| (0..depth).fold(ParamKind::Bool, |kind, _| ParamKind::Array(Box::new(kind)));
+		assert_eq!(nested(32).validate(), Ok(()));
+		assert_eq!(nested(33).validate().unwrap_err().kind, ErrorKind::LimitExceeded);
+	}
+
+	#[test]
+	fn test_int_width() {
+		assert_eq!(ParamKind::uint(8), Ok(ParamKind::Uint(8)));
+		assert_eq!(ParamKind::int(256), Ok(ParamKind::Int(256)));
+		for bits in [0, 7, 12, 264] {
+			assert_eq!(ParamKind::uint(bits).unwrap_err().kind, ErrorKind::InvalidType);
+			assert_eq!(ParamKind::int(bits).unwrap_err().kind, ErrorKind::InvalidType);
+		}
+	}
+
+	#[test]
+	fn test_display() {
+		let tuple = ParamKind::Tuple(vec![Box::new(ParamKind::Address), Box::new(ParamKind::Uint(256))]);
+		assert_eq!(ParamKind::Uint(256).to_string(), "uint256");
+		assert_eq!(ParamKind::Int(8).to_string(), "int8");
+		assert_eq!(ParamKind::FixedBytes(32).to_string(), "bytes32");
+		assert_eq!(ParamKind::Array(Box::new(ParamKind::String)).to_string(), "string[]");
+		assert_eq!(ParamKind::FixedArray(Box::new(tuple.clone()), 3).to_string(), "(address,uint256)[3]");
+		assert_eq!(
+			ParamKind::Tuple(vec![Box::new(ParamKind::Bool), Box::new(ParamKind::Array(Box::new(tuple)))]).to_string(),
+			"(bool,(address,uint256)[])"
+		);
+	}
 
 	#[test]
 	fn test_is_dynamic() {
@@ -85,4 +326,17 @@ mod tests {
 		assert_eq!(ParamKind::FixedArray(Box::new(ParamKind::String), 2).is_dynamic(), true);
 		assert_eq!(ParamKind::FixedArray(Box::new(ParamKind::Array(Box::new(ParamKind::Bool))), 2).is_dynamic(), true);
 	}
+
+	#[test]
+	fn test_struct_name() {
+		let names =
+			|internal_type: &str| ParamNames { internal_type: Some(internal_type.into()), ..ParamNames::default() };
+
+		assert_eq!(names("struct Order").struct_name(), Some("Order"));
+		assert_eq!(names("struct Exchange.Order[2][]").struct_name(), Some("Exchange.Order"));
+		assert_eq!(names("contract IERC20").struct_name(), None);
+		assert_eq!(ParamNames::named("order").struct_name(), None);
+		assert!(ParamNames::default().is_empty());
+		assert!(!ParamNames { components: vec![ParamNames::named("a")], ..ParamNames::default() }.is_empty());
+	}
 }
@@ -0,0 +1,329 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Parser for Solidity type strings and human-readable signatures.
+//!
+//! Accepts canonical types (`uint256`, `(address,bytes32)[3]`), the `tuple(...)`
+//! spelling, the `uint`/`int` aliases and params with `indexed` markers, data
+//! locations and names, like `event Transfer(address indexed from, address indexed to, uint256 value)`.
+use core::str::FromStr;
+
+use crate::std::{Box, String, Vec};
+use crate::{
+	Error, ErrorKind, FunctionParam, OwnedAbiError, OwnedEvent, OwnedFunction, Param, ParamKind, ParamNames,
+	StateMutability,
+};
+
+use ethabi_decode_syntax as syntax;
+
+pub(crate) use syntax::is_valid_int_width;
+
+impl From<syntax::SyntaxError> for Error {
+	fn from(error: syntax::SyntaxError) -> Self {
+		match error {
+			syntax::SyntaxError::InvalidType(pos) => Error::at(ErrorKind::InvalidType, pos),
+			syntax::SyntaxError::InvalidSignature(pos) => Error::at(ErrorKind::InvalidSignature, pos),
+			syntax::SyntaxError::LimitExceeded(pos) => Error::at(ErrorKind::LimitExceeded, pos),
+		}
+	}
+}
+
+/// Converts a parsed type, returning the names of its tuple components, or of
+/// the components of the tuples of an array.
+fn to_kind(kind: syntax::Kind) -> (ParamKind, Vec<ParamNames>) {
+	match kind {
+		syntax::Kind::Address => (ParamKind::Address, Vec::new()),
+		syntax::Kind::Bool => (ParamKind::Bool, Vec::new()),
+		syntax::Kind::String => (ParamKind::String, Vec::new()),
+		syntax::Kind::Bytes => (ParamKind::Bytes, Vec::new()),
+		syntax::Kind::Uint(bits) => (ParamKind::Uint(bits), Vec::new()),
+		syntax::Kind::Int(bits) => (ParamKind::Int(bits), Vec::new()),
+		syntax::Kind::FixedBytes(len) => (ParamKind::FixedBytes(len), Vec::new()),
+		syntax::Kind::Array(kind) => {
+			let (kind, components) = to_kind(*kind);
+			(ParamKind::Array(Box::new(kind)), components)
+		}
+		syntax::Kind::FixedArray(kind, len) => {
+			let (kind, components) = to_kind(*kind);
+			(ParamKind::FixedArray(Box::new(kind), len), components)
+		}
+		syntax::Kind::Tuple(components) => {
+			let components = components.into_iter().map(to_param).map(|param| (Box::new(param.kind), param.names));
+			let (kinds, names) = components.unzip();
+			(ParamKind::Tuple(kinds), names)
+		}
+	}
+}
+
+fn to_param(param: syntax::Param) -> Param {
+	let (kind, components) = to_kind(param.kind);
+	let name = param.name.map(String::from);
+	Param { kind, indexed: param.indexed, names: ParamNames { name, internal_type: None, components } }
+}
+
+fn to_function_param(param: syntax::Param) -> FunctionParam {
+	let Param { kind, names, .. } = to_param(param);
+	FunctionParam { kind, names }
+}
+
+impl FromStr for ParamKind {
+	type Err = Error;
+
+	/// Parses a type like `uint256`, `bytes32[]` or `tuple(uint8,bytes32[])[]`.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Ok(to_kind(syntax::parse_kind(s)?).0)
+	}
+}
+
+impl FromStr for Param {
+	type Err = Error;
+
+	/// Parses an event param like `address indexed from`.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Ok(to_param(syntax::parse_param(s)?))
+	}
+}
+
+impl FromStr for OwnedEvent {
+	type Err = Error;
+
+	/// Parses an event like `Transfer(address indexed,address indexed,uint256)` or
+	/// `event Transfer(address indexed from, address indexed to, uint256 value) anonymous`.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let event = syntax::parse_event(s)?;
+		let inputs = event.inputs.into_iter().map(to_param).collect();
+		Ok(OwnedEvent::new(event.name, inputs, event.anonymous))
+	}
+}
+
+impl FromStr for OwnedFunction {
+	type Err = Error;
+
+	/// Parses a function like `transfer(address,uint256)` or
+	/// `function balanceOf(address owner) external view returns (uint256)`.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let function = syntax::parse_function(s)?;
+		let inputs = function.inputs.into_iter().map(to_function_param).collect();
+		let outputs = function.outputs.into_iter().map(to_function_param).collect();
+		let state_mutability = match function.state_mutability {
+			syntax::Mutability::Pure => StateMutability::Pure,
+			syntax::Mutability::View => StateMutability::View,
+			syntax::Mutability::NonPayable => StateMutability::NonPayable,
+			syntax::Mutability::Payable => StateMutability::Payable,
+		};
+		Ok(OwnedFunction::new(function.name, inputs, outputs, state_mutability))
+	}
+}
+
+impl FromStr for OwnedAbiError {
+	type Err = Error;
+
+	/// Parses a custom error like `InsufficientBalance(uint256,uint256)` or
+	/// `error InsufficientBalance(uint256 available, uint256 required)`.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let error = syntax::parse_error(s)?;
+		let inputs = error.inputs.into_iter().map(to_function_param).collect();
+		Ok(OwnedAbiError::new(error.name, inputs))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		ErrorKind, FunctionParam, OwnedAbiError, OwnedEvent, OwnedFunction, Param, ParamKind, ParamNames,
+		StateMutability,
+	};
+
+	fn tuple(kinds: Vec<ParamKind>) -> ParamKind {
+		ParamKind::Tuple(kinds.into_iter().map(Box::new).collect())
+	}
+
+	#[test]
+	fn parse_elementary_types() {
+		assert_eq!("address".parse::<ParamKind>().unwrap(), ParamKind::Address);
+		assert_eq!("bool".parse::<ParamKind>().unwrap(), ParamKind::Bool);
+		assert_eq!("string".parse::<ParamKind>().unwrap(), ParamKind::String);
+		assert_eq!("bytes".parse::<ParamKind>().unwrap(), ParamKind::Bytes);
+		assert_eq!("bytes1".parse::<ParamKind>().unwrap(), ParamKind::FixedBytes(1));
+		assert_eq!("bytes32".parse::<ParamKind>().unwrap(), ParamKind::FixedBytes(32));
+		assert_eq!("uint8".parse::<ParamKind>().unwrap(), ParamKind::Uint(8));
+		assert_eq!("int256".parse::<ParamKind>().unwrap(), ParamKind::Int(256));
+		assert_eq!("uint".parse::<ParamKind>().unwrap(), ParamKind::Uint(256));
+		assert_eq!("int".parse::<ParamKind>().unwrap(), ParamKind::Int(256));
+	}
+
+	#[test]
+	fn parse_composite_types() {
+		assert_eq!(
+			"tuple(uint8,bytes32[])[]".parse::<ParamKind>().unwrap(),
+			ParamKind::Array(Box::new(tuple(vec![
+				ParamKind::Uint(8),
+				ParamKind::Array(Box::new(ParamKind::FixedBytes(32)))
+			])))
+		);
+		assert_eq!(
+			"(address,uint256)[3][]".parse::<ParamKind>().unwrap(),
+			ParamKind::Array(Box::new(ParamKind::FixedArray(
+				Box::new(tuple(vec![ParamKind::Address, ParamKind::Uint(256)])),
+				3
+			)))
+		);
+		assert_eq!(
+			"(bool,(string,address[2]))".parse::<ParamKind>().unwrap(),
+			tuple(vec![
+				ParamKind::Bool,
+				tuple(vec![ParamKind::String, ParamKind::FixedArray(Box::new(ParamKind::Address), 2)])
+			])
+		);
+	}
+
+	#[test]
+	fn parse_round_trips_display() {
+		for kind in ["uint256", "bytes4[]", "(address,(bool,string)[2])[]", "int8[3][4]"] {
+			assert_eq!(kind.parse::<ParamKind>().unwrap().to_string(), kind);
+		}
+	}
+
+	#[test]
+	fn parse_invalid_types() {
+		for (kind, offset) in [
+			("uint7", 0),
+			("uint264", 0),
+			("uint08", 0),
+			("int0", 0),
+			("bytes0", 0),
+			("bytes33", 0),
+			("float", 0),
+			("(uint256,foo)", 9),
+			("uint256[x]", 8),
+			("uint8[0]", 6),
+			("uint8[01]", 6),
+			("()", 0),
+			("(bool,())[]", 6),
+		] {
+			let error = kind.parse::<ParamKind>().unwrap_err();
+			assert_eq!(error.kind, ErrorKind::InvalidType, "{}", kind);
+			assert_eq!(error.offset, Some(offset), "{}", kind);
+		}
+
+		for kind in ["uint256[", "(uint256", "uint256 extra", ""] {
+			assert!(kind.parse::<ParamKind>().is_err(), "{}", kind);
+		}
+	}
+
+	#[test]
+	fn parse_nesting_depth() {
+		let nested = |depth: usize| format!("{}uint8{}", "(".repeat(depth), ")".repeat(depth));
+		assert!(nested(32).parse::<ParamKind>().is_ok());
+		let error = nested(33).parse::<ParamKind>().unwrap_err();
+		assert_eq!((error.kind, error.offset), (ErrorKind::LimitExceeded, Some(32)));
+
+		let arrays = |depth: usize| format!("(uint8{})", "[]".repeat(depth));
+		assert!(arrays(31).parse::<ParamKind>().is_ok());
+		assert_eq!(arrays(32).parse::<ParamKind>().unwrap_err().offset, Some(68));
+
+		// fails before recursing this deep, rather than overflowing the stack
+		let error = "(".repeat(1_000_000).parse::<ParamKind>().unwrap_err();
+		assert_eq!(error.kind, ErrorKind::LimitExceeded);
+		let error = format!("event Deep({} value)", nested(100)).parse::<OwnedEvent>().unwrap_err();
+		assert_eq!(error.kind, ErrorKind::LimitExceeded);
+
+		// the same limit `validate` enforces on types built by hand
+		let kind = (0..33).fold(ParamKind::Uint(8), |kind, _| tuple(vec![kind]));
+		assert_eq!(kind.validate().unwrap_err().kind, ErrorKind::LimitExceeded);
+	}
+
+	#[test]
+	fn parse_param() {
+		assert_eq!("address indexed from".parse::<Param>().unwrap(), Param::named("from", ParamKind::Address, true));
+		assert_eq!("uint256 value".parse::<Param>().unwrap(), Param::named("value", ParamKind::Uint(256), false));
+		assert_eq!("bytes".parse::<Param>().unwrap(), Param::new(ParamKind::Bytes, false));
+	}
+
+	#[test]
+	fn parse_event() {
+		let expected = OwnedEvent::new(
+			"Transfer",
+			vec![
+				Param::new(ParamKind::Address, true),
+				Param::new(ParamKind::Address, true),
+				Param::new(ParamKind::Uint(256), false),
+			],
+			false,
+		);
+
+		assert_eq!("Transfer(address indexed,address indexed,uint256)".parse::<OwnedEvent>().unwrap(), expected);
+		let event =
+			"event Transfer(address indexed from, address indexed to, uint256 value);".parse::<OwnedEvent>().unwrap();
+		assert_eq!(event.signature, expected.signature);
+		assert_eq!(event.inputs.iter().map(|p| p.name().unwrap()).collect::<Vec<_>>(), ["from", "to", "value"]);
+
+		let event = "event Deposit(tuple(uint8 a, bytes32[] b)[] deposits) anonymous".parse::<OwnedEvent>().unwrap();
+		assert_eq!(event.signature, "Deposit((uint8,bytes32[])[])");
+		assert!(event.anonymous);
+		let names = &event.inputs[0].names;
+		assert_eq!(names.name.as_deref(), Some("deposits"));
+		assert_eq!(names.components, vec![ParamNames::named("a"), ParamNames::named("b")]);
+
+		// `event` is a keyword, not a name
+		let error = "event(uint256)".parse::<OwnedEvent>().unwrap_err();
+		assert_eq!((error.kind, error.offset), (ErrorKind::InvalidSignature, Some(5)));
+		assert!("event event(uint256)".parse::<OwnedEvent>().is_err());
+
+		assert!("Transfer(address indexed".parse::<OwnedEvent>().is_err());
+		assert!("Transfer address".parse::<OwnedEvent>().is_err());
+	}
+
+	#[test]
+	fn parse_function() {
+		let function =
+			"function balanceOf(address owner) external view returns (uint256)".parse::<OwnedFunction>().unwrap();
+		let expected = OwnedFunction::new(
+			"balanceOf",
+			vec![FunctionParam::named("owner", ParamKind::Address)],
+			vec![FunctionParam::new(ParamKind::Uint(256))],
+			StateMutability::View,
+		);
+		assert_eq!(function, expected);
+
+		let function = "transfer(address,uint256)".parse::<OwnedFunction>().unwrap();
+		assert_eq!(function.signature, "transfer(address,uint256)");
+		assert_eq!(function.state_mutability, StateMutability::NonPayable);
+		assert!(function.outputs.is_empty());
+
+		let function = "function f(bytes calldata data, string memory s) payable".parse::<OwnedFunction>().unwrap();
+		assert_eq!(function.signature, "f(bytes,string)");
+		assert_eq!(function.state_mutability, StateMutability::Payable);
+
+		let error = "function f(uint256) constant".parse::<OwnedFunction>().unwrap_err();
+		assert_eq!(error.kind, ErrorKind::InvalidSignature);
+		assert_eq!(error.offset, Some(20));
+	}
+
+	#[test]
+	fn parse_abi_error() {
+		let expected = OwnedAbiError::new(
+			"InsufficientBalance",
+			vec![FunctionParam::new(ParamKind::Uint(256)), FunctionParam::new(ParamKind::Uint(256))],
+		);
+		assert_eq!("InsufficientBalance(uint256,uint256)".parse::<OwnedAbiError>().unwrap(), expected);
+
+		let named = OwnedAbiError::new(
+			"InsufficientBalance",
+			vec![
+				FunctionParam::named("available", ParamKind::Uint(256)),
+				FunctionParam::named("required", ParamKind::Uint(256)),
+			],
+		);
+		assert_eq!(
+			"error InsufficientBalance(uint256 available, uint256 required);".parse::<OwnedAbiError>().unwrap(),
+			named
+		);
+		assert!("error Unauthorized(address".parse::<OwnedAbiError>().is_err());
+	}
+}
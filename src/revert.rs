@@ -0,0 +1,232 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Standard revert payloads: `Error(string)` from `require` and `revert`, and
+//! `Panic(uint256)` from failed assertions and runtime checks.
+use core::fmt;
+
+use crate::std::{String, Vec};
+use crate::{decode, encode, Error, ErrorKind, ParamKind, Token, U256};
+
+/// Selector of `Error(string)`.
+pub const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Selector of `Panic(uint256)`.
+pub const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Reason given by `Panic(uint256)`, as listed in the Solidity docs.
+///
+/// Build codes with `From<U256>`, which only returns `Unknown` for codes that are
+/// not listed, as `decode_revert` does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanicCode {
+	/// 0x00: generic compiler inserted panic.
+	Generic,
+	/// 0x01: `assert` failed.
+	AssertionFailed,
+	/// 0x11: arithmetic overflow or underflow outside of an `unchecked` block.
+	ArithmeticOverflow,
+	/// 0x12: division or modulo by zero.
+	DivisionByZero,
+	/// 0x21: conversion of an out of range value to an enum.
+	InvalidEnumValue,
+	/// 0x22: access to an incorrectly encoded storage byte array.
+	InvalidStorageByteArray,
+	/// 0x31: `pop()` on an empty array.
+	EmptyArrayPop,
+	/// 0x32: array, `bytesN` or slice index out of bounds.
+	ArrayOutOfBounds,
+	/// 0x41: too much memory allocated, or an array too large.
+	OutOfMemory,
+	/// 0x51: call to a zero-initialized internal function variable.
+	UninitializedFunction,
+	/// Any other code.
+	Unknown(U256),
+}
+
+impl PanicCode {
+	/// Returns the numeric code.
+	pub fn code(&self) -> U256 {
+		match *self {
+			PanicCode::Generic => 0x00.into(),
+			PanicCode::AssertionFailed => 0x01.into(),
+			PanicCode::ArithmeticOverflow => 0x11.into(),
+			PanicCode::DivisionByZero => 0x12.into(),
+			PanicCode::InvalidEnumValue => 0x21.into(),
+			PanicCode::InvalidStorageByteArray => 0x22.into(),
+			PanicCode::EmptyArrayPop => 0x31.into(),
+			PanicCode::ArrayOutOfBounds => 0x32.into(),
+			PanicCode::OutOfMemory => 0x41.into(),
+			PanicCode::UninitializedFunction => 0x51.into(),
+			PanicCode::Unknown(code) => code,
+		}
+	}
+}
+
+impl From<U256> for PanicCode {
+	fn from(code: U256) -> Self {
+		if code > 0xff.into() {
+			return PanicCode::Unknown(code);
+		}
+		match code.low_u32() {
+			0x00 => PanicCode::Generic,
+			0x01 => PanicCode::AssertionFailed,
+			0x11 => PanicCode::ArithmeticOverflow,
+			0x12 => PanicCode::DivisionByZero,
+			0x21 => PanicCode::InvalidEnumValue,
+			0x22 => PanicCode::InvalidStorageByteArray,
+			0x31 => PanicCode::EmptyArrayPop,
+			0x32 => PanicCode::ArrayOutOfBounds,
+			0x41 => PanicCode::OutOfMemory,
+			0x51 => PanicCode::UninitializedFunction,
+			_ => PanicCode::Unknown(code),
+		}
+	}
+}
+
+impl fmt::Display for PanicCode {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let reason = match self {
+			PanicCode::Generic => "generic panic",
+			PanicCode::AssertionFailed => "assertion failed",
+			PanicCode::ArithmeticOverflow => "arithmetic overflow",
+			PanicCode::DivisionByZero => "division by zero",
+			PanicCode::InvalidEnumValue => "invalid enum value",
+			PanicCode::InvalidStorageByteArray => "invalid storage byte array",
+			PanicCode::EmptyArrayPop => "pop on empty array",
+			PanicCode::ArrayOutOfBounds => "array index out of bounds",
+			PanicCode::OutOfMemory => "out of memory",
+			PanicCode::UninitializedFunction => "uninitialized function",
+			PanicCode::Unknown(code) => return write!(f, "unknown panic {:#x}", code),
+		};
+		f.write_str(reason)
+	}
+}
+
+/// Decoded standard revert payload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Revert {
+	/// `Error(string)`, with the reason given to `require` or `revert`. Kept as
+	/// bytes like `Token::String`, as it is not checked to be UTF-8.
+	Error(Vec<u8>),
+	/// `Panic(uint256)`.
+	Panic(PanicCode),
+}
+
+impl Revert {
+	/// Returns the reason of an `Error(string)`, failing with `ErrorKind::InvalidUtf8`
+	/// if it is not valid UTF-8. `None` for panics.
+	pub fn reason(&self) -> Option<Result<&str, Error>> {
+		match self {
+			Revert::Error(reason) => Some(core::str::from_utf8(reason).map_err(|_| ErrorKind::InvalidUtf8.into())),
+			Revert::Panic(_) => None,
+		}
+	}
+
+	/// Returns the reason of an `Error(string)`, with invalid UTF-8 replaced by
+	/// `U+FFFD`. `None` for panics.
+	pub fn reason_lossy(&self) -> Option<String> {
+		match self {
+			Revert::Error(reason) => Some(String::from_utf8_lossy(reason).into_owned()),
+			Revert::Panic(_) => None,
+		}
+	}
+
+	/// Encodes the revert data, selector included.
+	pub fn encode(&self) -> Vec<u8> {
+		let (selector, token) = match self {
+			Revert::Error(reason) => (ERROR_SELECTOR, Token::String(reason.clone())),
+			Revert::Panic(code) => (PANIC_SELECTOR, Token::Uint(code.code())),
+		};
+		let mut data = selector.to_vec();
+		data.extend(encode(&[token]));
+		data
+	}
+}
+
+/// Decodes the data of a reverted call as `Error(string)` or `Panic(uint256)`.
+///
+/// Fails with `ErrorKind::SelectorMismatch` for other payloads, such as custom
+/// errors or the empty data of a bare `revert()`. The reason of an `Error(string)`
+/// is not checked to be UTF-8, see `Revert::reason`.
+pub fn decode_revert(data: &[u8]) -> Result<Revert, Error> {
+	let (selector, params) = data.split_first_chunk::<4>().ok_or(ErrorKind::SelectorMismatch)?;
+	match *selector {
+		ERROR_SELECTOR => match decode(&[ParamKind::String], params)?.pop() {
+			Some(Token::String(reason)) => Ok(Revert::Error(reason)),
+			_ => Err(ErrorKind::TypeMismatch.into()),
+		},
+		PANIC_SELECTOR => match decode(&[ParamKind::Uint(256)], params)?.pop() {
+			Some(Token::Uint(code)) => Ok(Revert::Panic(code.into())),
+			_ => Err(ErrorKind::TypeMismatch.into()),
+		},
+		_ => Err(ErrorKind::SelectorMismatch.into()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{decode_revert, PanicCode, Revert, ERROR_SELECTOR, PANIC_SELECTOR};
+	use crate::{util::keccak256, ErrorKind, U256};
+	use hex_literal::hex;
+
+	#[test]
+	fn test_selectors() {
+		assert_eq!(ERROR_SELECTOR, keccak256(b"Error(string)")[..4]);
+		assert_eq!(PANIC_SELECTOR, keccak256(b"Panic(uint256)")[..4]);
+	}
+
+	#[test]
+	fn test_decode_error() {
+		// revert("Not enough Ether provided.")
+		let data = hex!(
+			"
+			08c379a0
+			0000000000000000000000000000000000000000000000000000000000000020
+			000000000000000000000000000000000000000000000000000000000000001a
+			4e6f7420656e6f7567682045746865722070726f76696465642e000000000000
+		"
+		);
+		let revert = decode_revert(&data).unwrap();
+
+		assert_eq!(revert, Revert::Error(b"Not enough Ether provided.".to_vec()));
+		assert_eq!(revert.reason().unwrap(), Ok("Not enough Ether provided."));
+		assert_eq!(revert.encode(), data);
+	}
+
+	#[test]
+	fn test_decode_panic() {
+		let data = hex!("4e487b71 0000000000000000000000000000000000000000000000000000000000000011");
+		let revert = decode_revert(&data).unwrap();
+
+		assert_eq!(revert, Revert::Panic(PanicCode::ArithmeticOverflow));
+		assert_eq!(revert.encode(), data);
+
+		let unknown = Revert::Panic(PanicCode::Unknown(0x1234.into()));
+		assert_eq!(decode_revert(&unknown.encode()).unwrap(), unknown);
+		assert_eq!(PanicCode::from(U256::from(0x32)), PanicCode::ArrayOutOfBounds);
+		assert_eq!(PanicCode::Unknown(0x1234.into()).to_string(), "unknown panic 0x1234");
+		assert_eq!(revert.reason(), None);
+		assert_eq!(PanicCode::from(U256::from(0x13)), PanicCode::Unknown(0x13.into()));
+	}
+
+	#[test]
+	fn test_decode_errors() {
+		assert_eq!(decode_revert(&[]).unwrap_err().kind, ErrorKind::SelectorMismatch);
+		assert_eq!(decode_revert(&hex!("deadbeef")).unwrap_err().kind, ErrorKind::SelectorMismatch);
+		assert_eq!(decode_revert(&hex!("4e487b71")).unwrap_err().kind, ErrorKind::EmptyData);
+
+		// a reason that is not valid UTF-8 still decodes
+		let mut data = Revert::Error(b"ok".to_vec()).encode();
+		data[4 + 64] = 0xff;
+		let revert = decode_revert(&data).unwrap();
+		assert_eq!(revert, Revert::Error(b"\xffk".to_vec()));
+		assert_eq!(revert.reason().unwrap().unwrap_err().kind, ErrorKind::InvalidUtf8);
+		assert_eq!(revert.reason_lossy().unwrap(), "\u{fffd}k");
+	}
+}
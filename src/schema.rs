@@ -0,0 +1,517 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Definitions built from `&'static` slices, so they can be `const` or `static`.
+//!
+//! The `event!` and `function!` macros of the `derive` feature produce these
+//! from a human-readable signature, with the topic or selector computed at
+//! compile time.
+use crate::std::{Box, String, Vec};
+use crate::{
+	decoder::decode_shapes,
+	encoder::encode_shapes,
+	event::{decode_event_log, encode_event_log, encode_log_topics, EventParam},
+	signature::check_shapes,
+	tokenizable,
+	util::keccak256,
+	DecodeLimits, Error, ErrorKind, FunctionParam, OwnedEvent, OwnedFunction, Param, ParamKind, ParamNames,
+	StateMutability, Token, Tokenizable, H256,
+};
+use ethabi_decode_syntax::{Shape, TypeShape};
+
+/// Same as `ParamKind`, with nested types borrowed instead of boxed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StaticParamKind {
+	/// Address.
+	Address,
+	/// Bytes.
+	Bytes,
+	/// Signed integer.
+	Int(usize),
+	/// Unsigned integer.
+	Uint(usize),
+	/// Boolean.
+	Bool,
+	/// String.
+	String,
+	/// Array of unknown size.
+	Array(&'static StaticParamKind),
+	/// Vector of bytes with fixed size.
+	FixedBytes(usize),
+	/// Array with fixed size.
+	FixedArray(&'static StaticParamKind, usize),
+	/// Tuple containing different types
+	Tuple(&'static [StaticParamKind]),
+}
+
+impl StaticParamKind {
+	/// Converts to the `ParamKind` the encoder and decoder work with.
+	pub fn to_param_kind(&self) -> ParamKind {
+		match *self {
+			StaticParamKind::Address => ParamKind::Address,
+			StaticParamKind::Bytes => ParamKind::Bytes,
+			StaticParamKind::Int(bits) => ParamKind::Int(bits),
+			StaticParamKind::Uint(bits) => ParamKind::Uint(bits),
+			StaticParamKind::Bool => ParamKind::Bool,
+			StaticParamKind::String => ParamKind::String,
+			StaticParamKind::Array(kind) => ParamKind::Array(Box::new(kind.to_param_kind())),
+			StaticParamKind::FixedBytes(len) => ParamKind::FixedBytes(len),
+			StaticParamKind::FixedArray(kind, len) => ParamKind::FixedArray(Box::new(kind.to_param_kind()), len),
+			StaticParamKind::Tuple(kinds) => {
+				ParamKind::Tuple(kinds.iter().map(|kind| Box::new(kind.to_param_kind())).collect())
+			}
+		}
+	}
+}
+
+impl TypeShape for StaticParamKind {
+	type Component = StaticParamKind;
+
+	fn shape(&self) -> Shape<'_, Self, StaticParamKind> {
+		match *self {
+			StaticParamKind::Address => Shape::Address,
+			StaticParamKind::Bytes => Shape::Bytes,
+			StaticParamKind::Int(bits) => Shape::Int(bits),
+			StaticParamKind::Uint(bits) => Shape::Uint(bits),
+			StaticParamKind::Bool => Shape::Bool,
+			StaticParamKind::String => Shape::String,
+			StaticParamKind::Array(kind) => Shape::Array(kind),
+			StaticParamKind::FixedBytes(len) => Shape::FixedBytes(len),
+			StaticParamKind::FixedArray(kind, len) => Shape::FixedArray(kind, len),
+			StaticParamKind::Tuple(kinds) => Shape::Tuple(kinds),
+		}
+	}
+}
+
+impl AsRef<StaticParamKind> for StaticParamKind {
+	fn as_ref(&self) -> &StaticParamKind {
+		self
+	}
+}
+
+/// Same as `ParamNames`, with borrowed names and without the internal type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StaticParamNames {
+	/// Param name. Like "amount".
+	pub name: Option<&'static str>,
+	/// Names of the components of a tuple, or of the tuples of an array. Empty if unknown.
+	pub components: &'static [StaticParamNames],
+}
+
+impl StaticParamNames {
+	/// Converts to the `ParamNames` of owned definitions.
+	pub fn to_param_names(&self) -> ParamNames {
+		ParamNames {
+			name: self.name.map(String::from),
+			internal_type: None,
+			components: self.components.iter().map(StaticParamNames::to_param_names).collect(),
+		}
+	}
+}
+
+/// Same as `Param`, with a `StaticParamKind`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaticParam {
+	/// Param type.
+	pub kind: StaticParamKind,
+	/// Indexed flag. If true, param is used to build block bloom.
+	pub indexed: bool,
+	/// Names of the param and of its tuple components.
+	pub names: StaticParamNames,
+}
+
+impl StaticParam {
+	/// Returns the name of the param, if it has one.
+	pub fn name(&self) -> Option<&'static str> {
+		self.names.name
+	}
+}
+
+impl EventParam for StaticParam {
+	type Kind = StaticParamKind;
+
+	fn kind(&self) -> &StaticParamKind {
+		&self.kind
+	}
+
+	fn indexed(&self) -> bool {
+		self.indexed
+	}
+}
+
+/// Same as `FunctionParam`, with a `StaticParamKind`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaticFunctionParam {
+	/// Param type.
+	pub kind: StaticParamKind,
+	/// Names of the param and of its tuple components.
+	pub names: StaticParamNames,
+}
+
+impl StaticFunctionParam {
+	/// Returns the name of the param, if it has one.
+	pub fn name(&self) -> Option<&'static str> {
+		self.names.name
+	}
+
+	/// Converts to the `FunctionParam` of owned definitions.
+	pub fn to_function_param(&self) -> FunctionParam {
+		FunctionParam { kind: self.kind.to_param_kind(), names: self.names.to_param_names() }
+	}
+}
+
+fn kinds(params: &[StaticFunctionParam]) -> impl ExactSizeIterator<Item = &StaticParamKind> + Clone {
+	params.iter().map(|param| &param.kind)
+}
+
+/// Event with a `'static` definition and a precomputed topic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaticEvent {
+	/// Event signature. Like "Foo(int32,bytes)".
+	pub signature: &'static str,
+	/// Hash of the signature, the first topic of non-anonymous logs.
+	pub topic: H256,
+	/// Event input.
+	pub inputs: &'static [StaticParam],
+	/// If anonymous, event cannot be found using `from` filter.
+	pub anonymous: bool,
+}
+
+impl StaticEvent {
+	fn params(&self) -> Vec<Param> {
+		self.inputs
+			.iter()
+			.map(|p| Param { kind: p.kind.to_param_kind(), indexed: p.indexed, names: p.names.to_param_names() })
+			.collect()
+	}
+
+	/// Converts to an event owning its definition.
+	pub fn to_owned_event(&self) -> OwnedEvent {
+		OwnedEvent { signature: String::from(self.signature), inputs: self.params(), anonymous: self.anonymous }
+	}
+
+	/// Checks that `signature` is the canonical signature for `inputs`, see
+	/// `ethabi_decode::check_signature`, and that `topic` is its hash. Always holds
+	/// for events declared with `event!`.
+	pub fn check_signature(&self) -> Result<(), Error> {
+		check_shapes(self.signature, self.inputs.iter().map(|p| &p.kind))?;
+		match H256::from(keccak256(self.signature.as_bytes())) == self.topic {
+			true => Ok(()),
+			false => Err(ErrorKind::SignatureMismatch.into()),
+		}
+	}
+
+	/// Returns the topic logs of the event start with, `None` if it is anonymous.
+	fn signature_topic(&self) -> Option<H256> {
+		match self.anonymous {
+			true => None,
+			false => Some(self.topic),
+		}
+	}
+
+	/// Decodes an event log, see `Event::decode`. The first topic is compared to
+	/// `topic` rather than hashing the signature again, and the params are
+	/// decoded as they are, without building a `ParamKind` for each.
+	pub fn decode(&self, topics: Vec<H256>, data: Vec<u8>) -> Result<Vec<Token>, Error> {
+		self.decode_with_limits(topics, data, &DecodeLimits::default())
+	}
+
+	/// Decodes an event log, see `Event::decode_with_limits`.
+	pub fn decode_with_limits(
+		&self,
+		topics: Vec<H256>,
+		data: Vec<u8>,
+		limits: &DecodeLimits,
+	) -> Result<Vec<Token>, Error> {
+		decode_event_log(self.inputs, self.signature_topic(), topics, data, limits, false)
+	}
+
+	/// Decodes an event log, rejecting non-canonical encodings, see `Event::decode_strict`.
+	pub fn decode_strict(&self, topics: Vec<H256>, data: Vec<u8>) -> Result<Vec<Token>, Error> {
+		self.decode_strict_with_limits(topics, data, &DecodeLimits::default())
+	}
+
+	/// Same as `decode_strict`, but honouring the given `limits`.
+	pub fn decode_strict_with_limits(
+		&self,
+		topics: Vec<H256>,
+		data: Vec<u8>,
+		limits: &DecodeLimits,
+	) -> Result<Vec<Token>, Error> {
+		decode_event_log(self.inputs, self.signature_topic(), topics, data, limits, true)
+	}
+
+	/// Decodes an event log into a Rust type, see `Event::decode_as`.
+	pub fn decode_as<T: Tokenizable>(&self, topics: Vec<H256>, data: Vec<u8>) -> Result<T, Error> {
+		tokenizable::from_tokens(self.decode(topics, data)?)
+	}
+
+	/// Encodes a complete log, see `Event::encode_log`.
+	pub fn encode_log(&self, tokens: &[Token]) -> Result<(Vec<H256>, Vec<u8>), Error> {
+		encode_event_log(self.inputs, self.signature_topic(), tokens)
+	}
+
+	/// Encodes the topics of a log filter, see `Event::encode_topics`.
+	pub fn encode_topics(&self, indexed: &[Option<Token>]) -> Result<Vec<Option<H256>>, Error> {
+		encode_log_topics(self.inputs, self.signature_topic(), indexed)
+	}
+}
+
+/// Function with a `'static` definition and a precomputed selector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaticFunction {
+	/// Function signature. Like "transfer(address,uint256)".
+	pub signature: &'static str,
+	/// First four bytes of the signature hash, which prefix the calldata.
+	pub selector: [u8; 4],
+	/// Function input.
+	pub inputs: &'static [StaticFunctionParam],
+	/// Function output.
+	pub outputs: &'static [StaticFunctionParam],
+	/// Function state mutability.
+	pub state_mutability: StateMutability,
+}
+
+impl StaticFunction {
+	/// Converts to a function owning its definition.
+	pub fn to_owned_function(&self) -> OwnedFunction {
+		OwnedFunction {
+			signature: String::from(self.signature),
+			inputs: self.inputs.iter().map(StaticFunctionParam::to_function_param).collect(),
+			outputs: self.outputs.iter().map(StaticFunctionParam::to_function_param).collect(),
+			state_mutability: self.state_mutability,
+		}
+	}
+
+	/// Checks that `signature` is the canonical signature for `inputs`, see
+	/// `ethabi_decode::check_signature`, and that `selector` is taken from its hash.
+	/// Always holds for functions declared with `function!`.
+	pub fn check_signature(&self) -> Result<(), Error> {
+		check_shapes(self.signature, kinds(self.inputs))?;
+		match keccak256(self.signature.as_bytes())[..4] == self.selector {
+			true => Ok(()),
+			false => Err(ErrorKind::SignatureMismatch.into()),
+		}
+	}
+
+	/// Encodes the calldata for a call with the given input tokens, checking
+	/// them against `inputs` like `encode_params`.
+	pub fn encode_input(&self, tokens: &[Token]) -> Result<Vec<u8>, Error> {
+		let mut calldata = self.selector.to_vec();
+		calldata.extend(encode_shapes(kinds(self.inputs), tokens)?);
+		Ok(calldata)
+	}
+
+	/// Decodes calldata, checking that it starts with `selector`. Uses the default `DecodeLimits`.
+	pub fn decode_input(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+		self.decode_input_with_limits(data, &DecodeLimits::default())
+	}
+
+	/// Decodes calldata like `decode_input`, failing with `ErrorKind::LimitExceeded`
+	/// if it would decode to more than `limits` allows.
+	pub fn decode_input_with_limits(&self, data: &[u8], limits: &DecodeLimits) -> Result<Vec<Token>, Error> {
+		self.decode_calldata(data, limits, false)
+	}
+
+	/// Decodes calldata like `decode_input`, rejecting non-canonical encodings.
+	/// See `decode_strict` for the rules applied.
+	pub fn decode_input_strict(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+		self.decode_input_strict_with_limits(data, &DecodeLimits::default())
+	}
+
+	/// Same as `decode_input_strict`, but honouring the given `limits`.
+	pub fn decode_input_strict_with_limits(&self, data: &[u8], limits: &DecodeLimits) -> Result<Vec<Token>, Error> {
+		self.decode_calldata(data, limits, true)
+	}
+
+	fn decode_calldata(&self, data: &[u8], limits: &DecodeLimits, strict: bool) -> Result<Vec<Token>, Error> {
+		match data.split_first_chunk::<4>() {
+			Some((selector, params)) if *selector == self.selector => {
+				decode_shapes(kinds(self.inputs), params, limits, strict)
+			}
+			_ => Err(ErrorKind::SelectorMismatch.into()),
+		}
+	}
+
+	/// Encodes the data returned by the function, checking the tokens against
+	/// `outputs` like `encode_params`.
+	pub fn encode_output(&self, tokens: &[Token]) -> Result<Vec<u8>, Error> {
+		encode_shapes(kinds(self.outputs), tokens)
+	}
+
+	/// Decodes the data returned by the function. Uses the default `DecodeLimits`.
+	pub fn decode_output(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+		self.decode_output_with_limits(data, &DecodeLimits::default())
+	}
+
+	/// Decodes returned data like `decode_output`, failing with
+	/// `ErrorKind::LimitExceeded` if it would decode to more than `limits` allows.
+	pub fn decode_output_with_limits(&self, data: &[u8], limits: &DecodeLimits) -> Result<Vec<Token>, Error> {
+		decode_shapes(kinds(self.outputs), data, limits, false)
+	}
+
+	/// Decodes returned data like `decode_output`, rejecting non-canonical
+	/// encodings. See `decode_strict` for the rules applied.
+	pub fn decode_output_strict(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+		self.decode_output_strict_with_limits(data, &DecodeLimits::default())
+	}
+
+	/// Same as `decode_output_strict`, but honouring the given `limits`.
+	pub fn decode_output_strict_with_limits(&self, data: &[u8], limits: &DecodeLimits) -> Result<Vec<Token>, Error> {
+		decode_shapes(kinds(self.outputs), data, limits, true)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{StaticEvent, StaticFunction, StaticFunctionParam, StaticParam, StaticParamKind, StaticParamNames};
+	use crate::{DecodeLimits, ErrorKind, Param, ParamKind, PathSegment, StateMutability, Token, H256};
+	use hex_literal::hex;
+
+	const MESSAGE: StaticParamKind = StaticParamKind::Tuple(&[StaticParamKind::Address, StaticParamKind::Bytes]);
+
+	const fn named(name: &'static str) -> StaticParamNames {
+		StaticParamNames { name: Some(name), components: &[] }
+	}
+
+	static DISPATCHED: StaticEvent = StaticEvent {
+		signature: "Dispatched(uint64,(address,bytes)[])",
+		topic: H256(hex!("88895353ff88757e06f33a422da9d32a88eac89da820c08aed5988d08493f152")),
+		inputs: &[
+			StaticParam { kind: StaticParamKind::Uint(64), indexed: true, names: named("nonce") },
+			StaticParam {
+				kind: StaticParamKind::Array(&MESSAGE),
+				indexed: false,
+				names: StaticParamNames { name: None, components: &[named("recipient"), named("payload")] },
+			},
+		],
+		anonymous: false,
+	};
+
+	const BALANCE_OF: StaticFunction = StaticFunction {
+		signature: "balanceOf(address)",
+		selector: hex!("70a08231"),
+		inputs: &[StaticFunctionParam { kind: StaticParamKind::Address, names: named("owner") }],
+		outputs: &[StaticFunctionParam {
+			kind: StaticParamKind::Uint(256),
+			names: StaticParamNames { name: None, components: &[] },
+		}],
+		state_mutability: StateMutability::View,
+	};
+
+	#[test]
+	fn test_to_param_kind() {
+		let kind = StaticParamKind::FixedArray(&MESSAGE, 2).to_param_kind();
+		assert_eq!(kind.to_string(), "(address,bytes)[2]");
+
+		let event = DISPATCHED.to_owned_event();
+		assert_eq!(event.inputs[0], Param::named("nonce", ParamKind::Uint(64), true));
+		assert_eq!(event.inputs[1].name(), None);
+		let components = event.inputs[1].names.components.iter().map(|c| c.name.as_deref()).collect::<Vec<_>>();
+		assert_eq!(components, [Some("recipient"), Some("payload")]);
+		assert_eq!(event.as_event().check_signature(), Ok(()));
+
+		let function = BALANCE_OF.to_owned_function();
+		assert_eq!(function.inputs[0].name(), Some("owner"));
+		assert_eq!(function.outputs[0].name(), None);
+	}
+
+	#[test]
+	fn test_check_signature() {
+		assert_eq!(DISPATCHED.check_signature(), Ok(()));
+		assert_eq!(BALANCE_OF.check_signature(), Ok(()));
+
+		let wrong_topic = StaticEvent { topic: H256::zero(), ..DISPATCHED };
+		assert_eq!(wrong_topic.check_signature().unwrap_err().kind, ErrorKind::SignatureMismatch);
+		let wrong_selector = StaticFunction { selector: [0; 4], ..BALANCE_OF };
+		assert_eq!(wrong_selector.check_signature().unwrap_err().kind, ErrorKind::SignatureMismatch);
+
+		const UINT_OWNER: StaticFunctionParam =
+			StaticFunctionParam { kind: StaticParamKind::Uint(256), names: named("owner") };
+		let error = StaticFunction { inputs: &[UINT_OWNER], ..BALANCE_OF }.check_signature().unwrap_err();
+		assert_eq!(error.path.segments(), &[PathSegment::Input(0)]);
+	}
+
+	#[test]
+	fn test_event_round_trip() {
+		let tokens = vec![
+			Token::Uint(3.into()),
+			Token::Array(vec![Token::Tuple(vec![Token::Address([0x11u8; 20].into()), Token::Bytes(vec![1, 2])])]),
+		];
+		let (topics, data) = DISPATCHED.encode_log(&tokens).unwrap();
+
+		assert_eq!(topics[0], DISPATCHED.topic);
+		assert_eq!(DISPATCHED.encode_topics(&[None]).unwrap(), vec![Some(DISPATCHED.topic), None]);
+		assert_eq!(DISPATCHED.decode(topics.clone(), data.clone()).unwrap(), tokens);
+
+		let mut wrong = topics.clone();
+		wrong[0] = H256::zero();
+		assert_eq!(DISPATCHED.decode(wrong, data.clone()).unwrap_err().kind, ErrorKind::SignatureMismatch);
+		assert_eq!(DISPATCHED.decode(vec![], data).unwrap_err().kind, ErrorKind::TopicCountMismatch);
+	}
+
+	#[test]
+	fn test_event_limits_and_strict() {
+		let tokens = vec![Token::Uint(3.into()), Token::Array(vec![])];
+		let (mut topics, data) = DISPATCHED.encode_log(&tokens).unwrap();
+		assert_eq!(DISPATCHED.decode_strict(topics.clone(), data.clone()).unwrap(), tokens);
+
+		let limits = DecodeLimits { max_allocation: 32, ..DecodeLimits::default() };
+		let error = DISPATCHED.decode_with_limits(topics.clone(), data.clone(), &limits).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::LimitExceeded);
+		let error = DISPATCHED.decode_strict_with_limits(topics.clone(), data.clone(), &limits).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::LimitExceeded);
+
+		// a `uint64` topic with bits set above its width
+		topics[1].0[0] = 1;
+		assert!(DISPATCHED.decode(topics.clone(), data.clone()).is_ok());
+		assert_eq!(DISPATCHED.decode_strict(topics, data).unwrap_err().kind, ErrorKind::DirtyPadding);
+	}
+
+	#[test]
+	fn test_function() {
+		let calldata = BALANCE_OF.encode_input(&[Token::Address([0x11u8; 20].into())]).unwrap();
+		assert_eq!(calldata[..4], hex!("70a08231"));
+		assert_eq!(BALANCE_OF.decode_input(&calldata).unwrap(), vec![Token::Address([0x11u8; 20].into())]);
+		assert_eq!(BALANCE_OF.to_owned_function().as_function().selector(), BALANCE_OF.selector);
+
+		let output = BALANCE_OF.encode_output(&[Token::Uint(5.into())]).unwrap();
+		assert_eq!(BALANCE_OF.decode_output(&output).unwrap(), vec![Token::Uint(5.into())]);
+
+		let error = BALANCE_OF.encode_input(&[Token::Uint(1.into())]).unwrap_err();
+		assert_eq!((error.kind, error.path.segments()), (ErrorKind::TypeMismatch, &[PathSegment::Input(0)][..]));
+		assert_eq!(BALANCE_OF.encode_output(&[]).unwrap_err().kind, ErrorKind::TypeMismatch);
+	}
+
+	#[test]
+	fn test_function_limits_and_strict() {
+		let mut calldata = BALANCE_OF.encode_input(&[Token::Address([0x11u8; 20].into())]).unwrap();
+		let output = BALANCE_OF.encode_output(&[Token::Uint(5.into())]).unwrap();
+		assert_eq!(BALANCE_OF.decode_input_strict(&calldata).unwrap(), vec![Token::Address([0x11u8; 20].into())]);
+		assert_eq!(BALANCE_OF.decode_output_strict(&output).unwrap(), vec![Token::Uint(5.into())]);
+
+		let limits = DecodeLimits { max_allocation: 16, ..DecodeLimits::default() };
+		for error in [
+			BALANCE_OF.decode_input_with_limits(&calldata, &limits).unwrap_err(),
+			BALANCE_OF.decode_input_strict_with_limits(&calldata, &limits).unwrap_err(),
+			BALANCE_OF.decode_output_with_limits(&output, &limits).unwrap_err(),
+			BALANCE_OF.decode_output_strict_with_limits(&output, &limits).unwrap_err(),
+		] {
+			assert_eq!(error.kind, ErrorKind::LimitExceeded);
+		}
+
+		// an address with dirty padding, and returned data with a trailing word
+		calldata[4] = 1;
+		assert!(BALANCE_OF.decode_input(&calldata).is_ok());
+		assert_eq!(BALANCE_OF.decode_input_strict(&calldata).unwrap_err().kind, ErrorKind::DirtyPadding);
+		let padded = [output.clone(), vec![0; 32]].concat();
+		assert!(BALANCE_OF.decode_output(&padded).is_ok());
+		assert_eq!(BALANCE_OF.decode_output_strict(&padded).unwrap_err().kind, ErrorKind::NonCanonical);
+		assert_eq!(BALANCE_OF.decode_input_strict(&output).unwrap_err().kind, ErrorKind::SelectorMismatch);
+	}
+}
@@ -0,0 +1,193 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Canonical function and event signatures.
+use core::fmt::{self, Write};
+use ethabi_decode_syntax::{write_signature, write_type, TypeShape};
+use tiny_keccak::{Hasher, Keccak};
+
+use crate::std::String;
+use crate::{Error, ErrorKind, ParamKind, PathSegment, H256};
+
+/// Feeds whatever is written to it straight into a Keccak sponge.
+struct KeccakWriter(Keccak);
+
+impl Write for KeccakWriter {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.0.update(s.as_bytes());
+		Ok(())
+	}
+}
+
+/// Returns the canonical signature of a function or event, like `transfer(address,uint256)`.
+pub fn signature<'p, I>(name: &str, params: I) -> String
+where
+	I: IntoIterator<Item = &'p ParamKind>,
+{
+	let mut signature = String::new();
+	// writing to a `String` cannot fail
+	let _ = write_signature(&mut signature, name, params);
+	signature
+}
+
+/// Returns the name a signature starts with, like `transfer` for `transfer(address,uint256)`.
+pub fn name(signature: &str) -> &str {
+	signature.split('(').next().unwrap_or(signature)
+}
+
+/// Returns the Keccak-256 hash of the canonical signature, without allocating it.
+///
+/// For events this is the first topic, for functions its first four bytes are the selector.
+pub fn signature_hash<'p, I>(name: &str, params: I) -> H256
+where
+	I: IntoIterator<Item = &'p ParamKind>,
+{
+	let mut writer = KeccakWriter(Keccak::v256());
+	// writing to a `KeccakWriter` cannot fail
+	let _ = write_signature(&mut writer, name, params);
+	let mut result = [0u8; 32];
+	writer.0.finalize(&mut result);
+	result.into()
+}
+
+/// Returns the four byte selector of a function with the given name and params.
+pub fn selector<'p, I>(name: &str, params: I) -> [u8; 4]
+where
+	I: IntoIterator<Item = &'p ParamKind>,
+{
+	let mut selector = [0u8; 4];
+	selector.copy_from_slice(&signature_hash(name, params)[..4]);
+	selector
+}
+
+/// Splits `name(a,b,...)` into its name and the list of param types, only
+/// splitting on commas outside of tuple parentheses.
+fn split_signature(signature: &str) -> Option<(&str, impl Iterator<Item = &str>)> {
+	let open = signature.find('(')?;
+	let types = signature.get(open + 1..)?.strip_suffix(')')?;
+
+	let mut depth = 0usize;
+	let mut start = 0;
+	let mut done = types.is_empty();
+	let mut chars = types.char_indices();
+	let parts = core::iter::from_fn(move || {
+		if done {
+			return None;
+		}
+		for (i, c) in chars.by_ref() {
+			match c {
+				'(' => depth += 1,
+				')' => depth = depth.saturating_sub(1),
+				',' if depth == 0 => {
+					let part = &types[start..i];
+					start = i + 1;
+					return Some(part);
+				}
+				_ => {}
+			}
+		}
+		done = true;
+		Some(&types[start..])
+	});
+
+	Some((&signature[..open], parts))
+}
+
+/// Checks that `signature` is the canonical signature for `params`.
+///
+/// Fails with `ErrorKind::SignatureMismatch`, located at the first param whose
+/// type differs, or without a location if the signature is malformed or the
+/// number of params differs.
+pub fn check_signature<'p, I>(signature: &str, params: I) -> Result<(), Error>
+where
+	I: IntoIterator<Item = &'p ParamKind>,
+{
+	check_shapes(signature, params)
+}
+
+/// Same as `check_signature`, for param types of any `TypeShape`.
+pub(crate) fn check_shapes<'p, K, I>(signature: &str, params: I) -> Result<(), Error>
+where
+	K: TypeShape + 'p,
+	I: IntoIterator<Item = &'p K>,
+{
+	let (name, mut types) = split_signature(signature).ok_or(ErrorKind::SignatureMismatch)?;
+	if name.is_empty() {
+		return Err(ErrorKind::SignatureMismatch.into());
+	}
+
+	let mut expected = String::new();
+	for (i, kind) in params.into_iter().enumerate() {
+		let provided = types.next().ok_or(ErrorKind::SignatureMismatch)?;
+		expected.clear();
+		// writing to a `String` cannot fail
+		let _ = write_type(&mut expected, kind);
+		if provided != expected {
+			return Err(Error::new(ErrorKind::SignatureMismatch).within(PathSegment::Input(i)));
+		}
+	}
+
+	match types.next() {
+		Some(_) => Err(ErrorKind::SignatureMismatch.into()),
+		None => Ok(()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{check_signature, name, selector, signature, signature_hash};
+	use crate::{util::keccak256, ErrorKind, ParamKind, PathSegment, H256};
+	use hex_literal::hex;
+
+	#[test]
+	fn test_signature() {
+		let tuple = ParamKind::Tuple(vec![Box::new(ParamKind::Address), Box::new(ParamKind::Bytes)]);
+		let params = [ParamKind::Uint(256), ParamKind::FixedArray(Box::new(tuple), 3)];
+
+		assert_eq!(signature("foo", &params), "foo(uint256,(address,bytes)[3])");
+		assert_eq!(signature("bar", &[]), "bar()");
+	}
+
+	#[test]
+	fn test_name() {
+		assert_eq!(name("foo(uint256,(address,bytes)[3])"), "foo");
+		assert_eq!(name("bar()"), "bar");
+		assert_eq!(name(""), "");
+	}
+
+	#[test]
+	fn test_signature_hash() {
+		let params = [ParamKind::Address, ParamKind::Address, ParamKind::Uint(256)];
+
+		assert_eq!(signature_hash("Transfer", &params), H256::from(keccak256(b"Transfer(address,address,uint256)")));
+		assert_eq!(selector("transfer", &[ParamKind::Address, ParamKind::Uint(256)]), hex!("a9059cbb"));
+	}
+
+	#[test]
+	fn test_check_signature() {
+		let tuple = ParamKind::Tuple(vec![Box::new(ParamKind::Address), Box::new(ParamKind::Bytes)]);
+		let params = [ParamKind::Uint(256), tuple, ParamKind::Bool];
+
+		assert!(check_signature("foo(uint256,(address,bytes),bool)", &params).is_ok());
+		assert!(check_signature("bar()", &[]).is_ok());
+
+		let error = check_signature("foo(uint256,(address,bytes32),bool)", &params).unwrap_err();
+		assert_eq!(error.kind, ErrorKind::SignatureMismatch);
+		assert_eq!(error.path.segments(), &[PathSegment::Input(1)]);
+
+		let error = check_signature("foo(uint,(address,bytes),bool)", &params).unwrap_err();
+		assert_eq!(error.path.segments(), &[PathSegment::Input(0)]);
+
+		for signature in ["foo(uint256,(address,bytes))", "foo(uint256,(address,bytes),bool,bool)", "foo", "(uint256)"]
+		{
+			let error = check_signature(signature, &params).unwrap_err();
+			assert_eq!(error.kind, ErrorKind::SignatureMismatch);
+			assert!(error.path.is_empty());
+		}
+	}
+}
@@ -7,7 +7,7 @@
 // copied, modified, or distributed except according to those terms.
 
 #[cfg(not(feature = "std"))]
-pub use alloc::{boxed::Box, collections::btree_map::BTreeMap, vec, vec::Vec};
+pub use alloc::{boxed::Box, collections::btree_map::BTreeMap, string::String, vec, vec::Vec};
 
 #[cfg(feature = "std")]
-pub use std::{boxed::Box, collections::btree_map::BTreeMap, vec, vec::Vec};
+pub use std::{boxed::Box, collections::btree_map::BTreeMap, string::String, vec, vec::Vec};
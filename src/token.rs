@@ -8,7 +8,11 @@
 // copied, modified, or distributed except according to those terms.
 
 //! Ethereum ABI params.
-use crate::{Address, ParamKind, U256};
+use crate::{
+	param::is_valid_int_width,
+	util::{fits_int, fits_uint, sign_extend},
+	Address, ParamKind, U256,
+};
 
 use crate::std::Vec;
 
@@ -68,25 +72,18 @@ pub enum Token {
 impl Token {
 	/// Check whether the type of the token matches the given parameter type.
 	///
-	/// Numeric types (`Int` and `Uint`) type check if the size of the token
-	/// type is of greater or equal size than the provided parameter type.
+	/// Numeric types (`Int` and `Uint`) type check if the value fits the bit
+	/// width of the parameter type, which must be a multiple of 8 from 8 to 256.
+	/// `Int` values must be sign-extended to 256 bits.
 	pub fn type_check(&self, param_type: &ParamKind) -> bool {
 		match *self {
 			Token::Address(_) => *param_type == ParamKind::Address,
 			Token::Bytes(_) => *param_type == ParamKind::Bytes,
-			Token::Int(_) => {
-				if let ParamKind::Int(_) = *param_type {
-					true
-				} else {
-					false
-				}
+			Token::Int(int) => {
+				matches!(*param_type, ParamKind::Int(bits) if is_valid_int_width(bits) && fits_int(int, bits))
 			}
-			Token::Uint(_) => {
-				if let ParamKind::Uint(_) = *param_type {
-					true
-				} else {
-					false
-				}
+			Token::Uint(uint) => {
+				matches!(*param_type, ParamKind::Uint(bits) if is_valid_int_width(bits) && fits_uint(uint, bits))
 			}
 			Token::Bool(_) => *param_type == ParamKind::Bool,
 			Token::String(_) => *param_type == ParamKind::String,
@@ -113,7 +110,7 @@ impl Token {
 			}
 			Token::Tuple(ref tokens) => {
 				if let ParamKind::Tuple(ref param_type) = *param_type {
-					tokens.iter().enumerate().all(|(i, t)| t.type_check(&param_type[i]))
+					param_type.len() == tokens.len() && tokens.iter().zip(param_type).all(|(t, p)| t.type_check(p))
 				} else {
 					false
 				}
@@ -153,6 +150,34 @@ impl Token {
 		}
 	}
 
+	/// Converts an `Int` token to an `i128`, failing if it is not sign-extended
+	/// from 128 bits or less.
+	pub fn to_i128(self) -> Option<i128> {
+		match self {
+			Token::Int(int) if fits_int(int, 128) => Some(int.low_u128() as i128),
+			_ => None,
+		}
+	}
+
+	/// Converts an `Int` token to an `i64`, failing if it is not sign-extended
+	/// from 64 bits or less.
+	pub fn to_i64(self) -> Option<i64> {
+		match self {
+			Token::Int(int) if fits_int(int, 64) => Some(int.low_u64() as i64),
+			_ => None,
+		}
+	}
+
+	/// Creates an `Int` token, sign-extending `value` to 256 bits.
+	pub fn from_i128(value: i128) -> Token {
+		Token::Int(sign_extend(U256::from(value as u128), 128))
+	}
+
+	/// Creates an `Int` token, sign-extending `value` to 256 bits.
+	pub fn from_i64(value: i64) -> Token {
+		Token::from_i128(value.into())
+	}
+
 	/// Converts token to...
 	pub fn to_uint(self) -> Option<U256> {
 		match self {
@@ -211,9 +236,59 @@ impl Token {
 	}
 }
 
+/// Ethereum ABI params borrowing their bytes from the decoded input, see `decode_borrowed`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TokenRef<'a> {
+	/// Address.
+	Address(Address),
+	/// Bytes of known size, without their padding.
+	FixedBytes(&'a [u8]),
+	/// Bytes of unknown size.
+	Bytes(&'a [u8]),
+	/// Signed integer.
+	Int(U256),
+	/// Unsigned integer.
+	Uint(U256),
+	/// Boolean value.
+	Bool(bool),
+	/// String, not checked to be utf8.
+	String(&'a [u8]),
+	/// Array with known size.
+	FixedArray(Vec<TokenRef<'a>>),
+	/// Array of params with unknown size.
+	Array(Vec<TokenRef<'a>>),
+	/// Tuple of params of variable types.
+	Tuple(Vec<TokenRef<'a>>),
+}
+
+impl TokenRef<'_> {
+	/// Copies the borrowed bytes, converting to an owned `Token`.
+	pub fn into_owned(self) -> Token {
+		self.into()
+	}
+}
+
+impl From<TokenRef<'_>> for Token {
+	fn from(token: TokenRef<'_>) -> Self {
+		let into_owned = |tokens: Vec<TokenRef>| tokens.into_iter().map(Token::from).collect();
+		match token {
+			TokenRef::Address(address) => Token::Address(address),
+			TokenRef::FixedBytes(bytes) => Token::FixedBytes(bytes.to_vec()),
+			TokenRef::Bytes(bytes) => Token::Bytes(bytes.to_vec()),
+			TokenRef::Int(int) => Token::Int(int),
+			TokenRef::Uint(uint) => Token::Uint(uint),
+			TokenRef::Bool(b) => Token::Bool(b),
+			TokenRef::String(s) => Token::String(s.to_vec()),
+			TokenRef::FixedArray(tokens) => Token::FixedArray(into_owned(tokens)),
+			TokenRef::Array(tokens) => Token::Array(into_owned(tokens)),
+			TokenRef::Tuple(tokens) => Token::Tuple(into_owned(tokens)),
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use crate::{ParamKind, Token};
+	use crate::{ParamKind, Token, U256};
 
 	#[test]
 	fn test_type_check() {
@@ -270,6 +345,44 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn test_type_check_int_width() {
+		assert!(Token::Uint(0xff.into()).type_check(&ParamKind::Uint(8)));
+		assert!(!Token::Uint(0x100.into()).type_check(&ParamKind::Uint(8)));
+		assert!(Token::Uint(U256::MAX).type_check(&ParamKind::Uint(256)));
+
+		assert!(Token::Int(U256::MAX).type_check(&ParamKind::Int(8)));
+		assert!(Token::from_i128(-128).type_check(&ParamKind::Int(8)));
+		assert!(!Token::from_i128(-129).type_check(&ParamKind::Int(8)));
+		assert!(!Token::Int(0x80.into()).type_check(&ParamKind::Int(8)));
+
+		for bits in [0, 7, 264] {
+			assert!(!Token::Uint(0.into()).type_check(&ParamKind::Uint(bits)));
+			assert!(!Token::Int(0.into()).type_check(&ParamKind::Int(bits)));
+		}
+
+		let tuple = ParamKind::Tuple(vec![Box::new(ParamKind::Bool)]);
+		assert!(!Token::Tuple(vec![Token::Bool(true), Token::Bool(true)]).type_check(&tuple));
+		assert!(!Token::Tuple(vec![]).type_check(&tuple));
+	}
+
+	#[test]
+	fn test_signed_conversions() {
+		assert_eq!(Token::from_i128(-1), Token::Int(U256::MAX));
+		assert_eq!(Token::from_i128(5), Token::Int(5.into()));
+		assert_eq!(Token::from_i64(-1), Token::Int(U256::MAX));
+		assert_eq!(Token::from_i64(i64::MIN).to_i64(), Some(i64::MIN));
+		assert_eq!(Token::from_i128(i128::MIN).to_i128(), Some(i128::MIN));
+		assert_eq!(Token::from_i128(-2).to_i64(), Some(-2));
+		assert_eq!(Token::from_i128(i64::MAX as i128 + 1).to_i64(), None);
+		assert_eq!(Token::from_i128(i64::MIN as i128).to_i64(), Some(i64::MIN));
+
+		// a positive value of more than 127 bits does not fit
+		assert_eq!(Token::Int(U256::one() << 127).to_i128(), None);
+		assert_eq!(Token::Int(U256::MAX << 127).to_i128(), Some(i128::MIN));
+		assert_eq!(Token::Uint(5.into()).to_i128(), None);
+	}
+
 	#[test]
 	fn test_is_dynamic() {
 		assert_eq!(Token::Address("0000000000000000000000000000000000000000".parse().unwrap()).is_dynamic(), false);
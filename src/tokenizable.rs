@@ -0,0 +1,329 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Conversion between tokens and Rust types.
+use crate::std::{vec, Vec};
+use crate::{
+	decoder::decode_shapes, Address, DecodeLimits, Error, ErrorKind, ParamKind, PathSegment, StaticParamKind, Token,
+	H256, U256,
+};
+
+/// Rust type with a fixed ABI type, convertible to and from a `Token`.
+pub trait Tokenizable: Sized {
+	/// ABI type of the values of this type, usable in `const` items.
+	const KIND: StaticParamKind;
+
+	/// Returns the ABI type of the values of this type, see `KIND`.
+	fn param_kind() -> ParamKind {
+		Self::KIND.to_param_kind()
+	}
+
+	/// Converts a token, failing with `ErrorKind::TypeMismatch` if it is not of the right type.
+	fn from_token(token: Token) -> Result<Self, Error>;
+
+	/// Converts into a token.
+	fn into_token(self) -> Token;
+}
+
+/// Types of the top-level params `T` is decoded from: the components of a tuple, or `T` alone.
+fn param_kinds<T: Tokenizable>() -> &'static [StaticParamKind] {
+	match T::KIND {
+		StaticParamKind::Tuple(components) => components,
+		_ => core::slice::from_ref(&T::KIND),
+	}
+}
+
+/// Converts the tokens of the params returned by `param_kinds`.
+pub(crate) fn from_tokens<T: Tokenizable>(mut tokens: Vec<Token>) -> Result<T, Error> {
+	match T::KIND {
+		StaticParamKind::Tuple(_) => T::from_token(Token::Tuple(tokens)),
+		_ if tokens.len() == 1 => T::from_token(tokens.remove(0)),
+		_ => Err(ErrorKind::TypeMismatch.into()),
+	}
+}
+
+/// Decodes data into a Rust type, using its `Tokenizable::KIND` as the schema.
+///
+/// A tuple stands for the list of params, so `decode_as::<(Address, U256)>` decodes
+/// an address followed by a uint256. A single tuple param is decoded as a 1-tuple
+/// wrapping it, like `decode_as::<((Address, U256),)>`.
+pub fn decode_as<T: Tokenizable>(data: &[u8]) -> Result<T, Error> {
+	from_tokens(decode_shapes(param_kinds::<T>().iter(), data, &DecodeLimits::default(), false)?)
+}
+
+fn mismatch<T>() -> Result<T, Error> {
+	Err(ErrorKind::TypeMismatch.into())
+}
+
+impl Tokenizable for Address {
+	const KIND: StaticParamKind = StaticParamKind::Address;
+
+	fn from_token(token: Token) -> Result<Self, Error> {
+		match token {
+			Token::Address(address) => Ok(address),
+			_ => mismatch(),
+		}
+	}
+
+	fn into_token(self) -> Token {
+		Token::Address(self)
+	}
+}
+
+impl Tokenizable for U256 {
+	const KIND: StaticParamKind = StaticParamKind::Uint(256);
+
+	fn from_token(token: Token) -> Result<Self, Error> {
+		match token {
+			Token::Uint(uint) => Ok(uint),
+			_ => mismatch(),
+		}
+	}
+
+	fn into_token(self) -> Token {
+		Token::Uint(self)
+	}
+}
+
+impl Tokenizable for bool {
+	const KIND: StaticParamKind = StaticParamKind::Bool;
+
+	fn from_token(token: Token) -> Result<Self, Error> {
+		match token {
+			Token::Bool(b) => Ok(b),
+			_ => mismatch(),
+		}
+	}
+
+	fn into_token(self) -> Token {
+		Token::Bool(self)
+	}
+}
+
+impl Tokenizable for H256 {
+	const KIND: StaticParamKind = StaticParamKind::FixedBytes(32);
+
+	fn from_token(token: Token) -> Result<Self, Error> {
+		match token {
+			Token::FixedBytes(bytes) if bytes.len() == 32 => Ok(H256::from_slice(&bytes)),
+			_ => mismatch(),
+		}
+	}
+
+	fn into_token(self) -> Token {
+		Token::FixedBytes(self.as_bytes().to_vec())
+	}
+}
+
+impl Tokenizable for Vec<u8> {
+	const KIND: StaticParamKind = StaticParamKind::Bytes;
+
+	fn from_token(token: Token) -> Result<Self, Error> {
+		match token {
+			Token::Bytes(bytes) => Ok(bytes),
+			_ => mismatch(),
+		}
+	}
+
+	fn into_token(self) -> Token {
+		Token::Bytes(self)
+	}
+}
+
+impl<const N: usize> Tokenizable for [u8; N] {
+	const KIND: StaticParamKind = {
+		assert!(matches!(N, 1..=32), "`[u8; N]` stands for `bytesN`, which needs N from 1 to 32");
+		StaticParamKind::FixedBytes(N)
+	};
+
+	fn from_token(token: Token) -> Result<Self, Error> {
+		// fails to compile for an `N` without a `bytesN` type
+		let _ = Self::KIND;
+		match token {
+			Token::FixedBytes(bytes) => bytes.try_into().or_else(|_| mismatch()),
+			_ => mismatch(),
+		}
+	}
+
+	fn into_token(self) -> Token {
+		let _ = Self::KIND;
+		Token::FixedBytes(self.to_vec())
+	}
+}
+
+/// Converts every token, locating errors at the element they occurred in.
+fn from_elements<T: Tokenizable>(tokens: Vec<Token>) -> Result<Vec<T>, Error> {
+	tokens
+		.into_iter()
+		.enumerate()
+		.map(|(i, token)| T::from_token(token).map_err(|e| e.within(PathSegment::Element(i))))
+		.collect()
+}
+
+impl<T: Tokenizable> Tokenizable for Vec<T> {
+	const KIND: StaticParamKind = StaticParamKind::Array(&T::KIND);
+
+	fn from_token(token: Token) -> Result<Self, Error> {
+		match token {
+			Token::Array(tokens) => from_elements(tokens),
+			_ => mismatch(),
+		}
+	}
+
+	fn into_token(self) -> Token {
+		Token::Array(self.into_iter().map(T::into_token).collect())
+	}
+}
+
+impl<T: Tokenizable, const N: usize> Tokenizable for [T; N] {
+	const KIND: StaticParamKind = StaticParamKind::FixedArray(&T::KIND, N);
+
+	fn from_token(token: Token) -> Result<Self, Error> {
+		match token {
+			Token::FixedArray(tokens) if tokens.len() == N => from_elements(tokens)?.try_into().or_else(|_| mismatch()),
+			_ => mismatch(),
+		}
+	}
+
+	fn into_token(self) -> Token {
+		Token::FixedArray(self.into_iter().map(T::into_token).collect())
+	}
+}
+
+macro_rules! impl_tuple {
+	($count: expr, $( $ty: ident $index: tt ),+) => {
+		impl<$( $ty: Tokenizable ),+> Tokenizable for ($( $ty, )+) {
+			const KIND: StaticParamKind = StaticParamKind::Tuple(&[$( $ty::KIND ),+]);
+
+			fn from_token(token: Token) -> Result<Self, Error> {
+				let mut tokens = match token {
+					Token::Tuple(tokens) if tokens.len() == $count => tokens.into_iter(),
+					_ => return mismatch(),
+				};
+				Ok(($(
+					$ty::from_token(tokens.next().ok_or(ErrorKind::TypeMismatch)?)
+						.map_err(|e| e.within(PathSegment::Component($index)))?,
+				)+))
+			}
+
+			fn into_token(self) -> Token {
+				Token::Tuple(vec![$( self.$index.into_token() ),+])
+			}
+		}
+	};
+}
+
+impl_tuple!(1, A 0);
+impl_tuple!(2, A 0, B 1);
+impl_tuple!(3, A 0, B 1, C 2);
+impl_tuple!(4, A 0, B 1, C 2, D 3);
+impl_tuple!(5, A 0, B 1, C 2, D 3, E 4);
+impl_tuple!(6, A 0, B 1, C 2, D 3, E 4, F 5);
+impl_tuple!(7, A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_tuple!(8, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+/// Support for the code generated by `ethabi-decode-derive`, not part of the public API.
+#[doc(hidden)]
+pub mod derive {
+	use crate::std::vec::IntoIter;
+	use crate::{Error, ErrorKind, PathSegment, StaticParamKind, Token, Tokenizable};
+
+	pub use crate::std::Vec;
+
+	/// Whether two kinds are the same type, usable in `const` items.
+	pub const fn same_kind(a: &StaticParamKind, b: &StaticParamKind) -> bool {
+		use StaticParamKind::*;
+
+		match (a, b) {
+			(Address, Address) | (Bool, Bool) | (String, String) | (Bytes, Bytes) => true,
+			(Uint(a), Uint(b)) | (Int(a), Int(b)) | (FixedBytes(a), FixedBytes(b)) => *a == *b,
+			(Array(a), Array(b)) => same_kind(a, b),
+			(FixedArray(a, m), FixedArray(b, n)) => *m == *n && same_kind(a, b),
+			(Tuple(a), Tuple(b)) => {
+				if a.len() != b.len() {
+					return false;
+				}
+				let mut i = 0;
+				while i < a.len() {
+					if !same_kind(&a[i], &b[i]) {
+						return false;
+					}
+					i += 1;
+				}
+				true
+			}
+			_ => false,
+		}
+	}
+
+	/// Checks there are `len` tokens, then hands them out in order.
+	pub fn tokens(tokens: Vec<Token>, len: usize) -> Result<IntoIter<Token>, Error> {
+		match tokens.len() == len {
+			true => Ok(tokens.into_iter()),
+			false => Err(ErrorKind::TypeMismatch.into()),
+		}
+	}
+
+	/// Same as `tokens`, for the components of a tuple token.
+	pub fn components(token: Token, len: usize) -> Result<IntoIter<Token>, Error> {
+		match token {
+			Token::Tuple(components) => tokens(components, len),
+			_ => Err(ErrorKind::TypeMismatch.into()),
+		}
+	}
+
+	/// Converts the next token, locating errors at `segment`.
+	pub fn next<T: Tokenizable>(tokens: &mut IntoIter<Token>, segment: PathSegment) -> Result<T, Error> {
+		let token = tokens.next().ok_or(ErrorKind::TypeMismatch)?;
+		T::from_token(token).map_err(|e| e.within(segment))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{decode_as, Tokenizable};
+	use crate::{encode, Address, ErrorKind, ParamKind, PathSegment, Token, U256};
+
+	#[test]
+	fn test_decode_as() {
+		let address = Address::from([0x11u8; 20]);
+		let encoded = encode(&[Token::Address(address), Token::Uint(7.into()), Token::Bytes(b"abc".to_vec())]);
+
+		let (to, amount, payload) = decode_as::<(Address, U256, Vec<u8>)>(&encoded).unwrap();
+		assert_eq!((to, amount, payload), (address, U256::from(7), b"abc".to_vec()));
+
+		// a single param does not need to be wrapped in a tuple
+		assert!(decode_as::<bool>(&encode(&[Token::Bool(true)])).unwrap());
+	}
+
+	#[test]
+	fn test_round_trip() {
+		type Nested = (Vec<[u8; 4]>, [bool; 2], ((Address, U256),));
+		let value: Nested = (vec![[1, 2, 3, 4]], [true, false], ((Address::from([0x22u8; 20]), 9.into()),));
+
+		assert_eq!(<Nested as Tokenizable>::param_kind().to_string(), "(bytes4[],bool[2],((address,uint256)))");
+		let encoded = encode(&[value.clone().into_token()]);
+		assert_eq!(decode_as::<(Nested,)>(&encoded).unwrap().0, value);
+	}
+
+	#[test]
+	fn test_type_mismatch() {
+		let error = <(bool, Vec<U256>)>::from_token(Token::Tuple(vec![
+			Token::Bool(true),
+			Token::Array(vec![Token::Uint(1.into()), Token::Bool(false)]),
+		]))
+		.unwrap_err();
+		assert_eq!(error.kind, ErrorKind::TypeMismatch);
+		assert_eq!(error.path.segments(), &[PathSegment::Component(1), PathSegment::Element(1)]);
+
+		assert!(<[u8; 4]>::from_token(Token::FixedBytes(vec![1, 2])).is_err());
+		assert!(<[bool; 2]>::from_token(Token::FixedArray(vec![Token::Bool(true)])).is_err());
+		assert!(<(bool, bool)>::from_token(Token::Tuple(vec![Token::Bool(true)])).is_err());
+		assert_eq!(<[u8; 3]>::param_kind(), ParamKind::FixedBytes(3));
+	}
+}
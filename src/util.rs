@@ -8,25 +8,42 @@
 
 //! Utils used by different modules.
 
-use crate::{Error, Word};
+use crate::{Error, ErrorKind, Word, U256};
+use tiny_keccak::{Hasher, Keccak};
 
-use crate::std::Vec;
+/// Bytes with len equal n * 32, read as words in place.
+#[derive(Debug, Clone, Copy)]
+pub struct Words<'a>(&'a [u8]);
 
-/// Converts a vector of bytes with len equal n * 32, to a vector of slices.
-pub fn slice_data(data: &[u8]) -> Result<Vec<Word>, Error> {
-	if data.len() % 32 != 0 {
-		return Err(Error::InvalidData);
+impl<'a> Words<'a> {
+	/// Returns the number of words.
+	pub fn len(&self) -> usize {
+		self.0.len() / 32
 	}
 
-	let times = data.len() / 32;
-	let mut result = Vec::with_capacity(times);
-	for i in 0..times {
-		let mut slice = [0u8; 32];
-		let offset = 32 * i;
-		slice.copy_from_slice(&data[offset..offset + 32]);
-		result.push(slice);
+	/// Returns the word at `position`.
+	pub fn get(&self, position: usize) -> Option<&'a Word> {
+		let start = position.checked_mul(32)?;
+		self.0.get(start..start.checked_add(32)?)?.try_into().ok()
+	}
+
+	/// Returns the words from `position` on.
+	pub fn tail(&self, position: usize) -> Option<Words<'a>> {
+		self.0.get(position.checked_mul(32)?..).map(Words)
+	}
+
+	/// Returns the bytes of the words from `start` up to `end`.
+	pub fn bytes(&self, start: usize, end: usize) -> Option<&'a [u8]> {
+		self.0.get(start.checked_mul(32)?..end.checked_mul(32)?)
+	}
+}
+
+/// Views a slice of bytes with len equal n * 32 as words, without copying.
+pub fn slice_data(data: &[u8]) -> Result<Words<'_>, Error> {
+	match data.len() % 32 {
+		0 => Ok(Words(data)),
+		_ => Err(ErrorKind::UnalignedData.into()),
 	}
-	Ok(result)
 }
 
 /// Converts a u32 to a right aligned array of 32 bytes.
@@ -36,11 +53,62 @@ pub fn pad_u32(value: u32) -> Word {
 	padded
 }
 
+/// Sign-extends the two's complement value held in the low `bits` bits of `value` to 256 bits.
+pub fn sign_extend(value: U256, bits: usize) -> U256 {
+	if bits == 0 || bits >= 256 {
+		return value;
+	}
+	let mask = (U256::one() << bits) - 1;
+	match value.bit(bits - 1) {
+		true => value | !mask,
+		false => value & mask,
+	}
+}
+
+/// Returns whether `value` is an `int<bits>` sign-extended to 256 bits.
+pub fn fits_int(value: U256, bits: usize) -> bool {
+	sign_extend(value, bits) == value
+}
+
+/// Returns whether `value` is a `uint<bits>`, with no bits set above the low `bits`.
+pub fn fits_uint(value: U256, bits: usize) -> bool {
+	bits >= 256 || (value >> bits).is_zero()
+}
+
+/// Computes the Keccak-256 hash of `data`.
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+	let mut result = [0u8; 32];
+	let mut sponge = Keccak::v256();
+	sponge.update(data);
+	sponge.finalize(&mut result);
+	result
+}
+
 #[cfg(test)]
 mod tests {
-	use super::pad_u32;
+	use super::{fits_int, fits_uint, pad_u32, sign_extend};
+	use crate::U256;
 	use hex_literal::hex;
 
+	#[test]
+	fn test_sign_extend() {
+		let minus_one = U256::MAX;
+		assert_eq!(sign_extend(0xff.into(), 8), minus_one);
+		assert_eq!(sign_extend(0x7f.into(), 8), 0x7f.into());
+		assert_eq!(sign_extend(0x1ff.into(), 8), minus_one);
+		assert_eq!(sign_extend(minus_one, 256), minus_one);
+
+		assert!(fits_int(minus_one, 8));
+		assert!(fits_int(0x7f.into(), 8));
+		assert!(!fits_int(0x80.into(), 8));
+		assert!(fits_int(0x80.into(), 16));
+		assert!(!fits_int(minus_one - 0x80, 8));
+
+		assert!(fits_uint(0xff.into(), 8));
+		assert!(!fits_uint(0x100.into(), 8));
+		assert!(fits_uint(minus_one, 256));
+	}
+
 	#[test]
 	fn test_pad_u32() {
 		// this will fail if endianness is not supported
@@ -0,0 +1,534 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Syntax tree of Solidity type strings and human-readable signatures, their
+//! parser, and the rendering of canonical types.
+//!
+//! Shared by `ethabi-decode` and `ethabi-decode-derive`, so that signatures are
+//! parsed and rendered at compile time exactly like the `FromStr` and `Display`
+//! impls do at runtime.
+#![no_std]
+#![warn(missing_docs)]
+
+extern crate alloc;
+
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt;
+
+/// Solidity type, with aliases like `uint` resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Kind<'s> {
+	/// `address`.
+	Address,
+	/// `bool`.
+	Bool,
+	/// `string`.
+	String,
+	/// `bytes`.
+	Bytes,
+	/// `uintN`, with its width in bits.
+	Uint(usize),
+	/// `intN`, with its width in bits.
+	Int(usize),
+	/// `bytesN`, with its length from 1 to 32.
+	FixedBytes(usize),
+	/// `T[]`.
+	Array(Box<Kind<'s>>),
+	/// `T[N]`, with its length of at least 1.
+	FixedArray(Box<Kind<'s>>, usize),
+	/// Tuple, with the name given to each component.
+	Tuple(Vec<Param<'s>>),
+}
+
+/// Param, or tuple component, with its name if it has one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Param<'s> {
+	/// Param type.
+	pub kind: Kind<'s>,
+	/// Whether the param was marked `indexed`, only allowed for event inputs.
+	pub indexed: bool,
+	/// Param name, borrowed from the parsed string.
+	pub name: Option<&'s str>,
+}
+
+impl<'s> AsRef<Kind<'s>> for Param<'s> {
+	fn as_ref(&self) -> &Kind<'s> {
+		&self.kind
+	}
+}
+
+/// Event, as returned by `parse_event`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event<'s> {
+	/// Event name.
+	pub name: &'s str,
+	/// Event inputs, in order.
+	pub inputs: Vec<Param<'s>>,
+	/// Whether the event was marked `anonymous`.
+	pub anonymous: bool,
+}
+
+/// Function, as returned by `parse_function`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Function<'s> {
+	/// Function name.
+	pub name: &'s str,
+	/// Function inputs, in order.
+	pub inputs: Vec<Param<'s>>,
+	/// Function outputs, empty without a `returns` clause.
+	pub outputs: Vec<Param<'s>>,
+	/// State mutability, `NonPayable` unless given.
+	pub state_mutability: Mutability,
+}
+
+/// Custom error, as returned by `parse_error`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AbiError<'s> {
+	/// Error name.
+	pub name: &'s str,
+	/// Error inputs, in order.
+	pub inputs: Vec<Param<'s>>,
+}
+
+/// State mutability keyword of a function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mutability {
+	/// `pure`.
+	Pure,
+	/// `view`.
+	View,
+	/// No keyword, or `nonpayable`.
+	NonPayable,
+	/// `payable`.
+	Payable,
+}
+
+/// Syntax error, with the byte offset it was found at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyntaxError {
+	/// Not a valid type.
+	InvalidType(usize),
+	/// Not a valid param list or signature.
+	InvalidSignature(usize),
+	/// Arrays and tuples nested deeper than `MAX_DEPTH`.
+	LimitExceeded(usize),
+}
+
+type Result<T> = core::result::Result<T, SyntaxError>;
+
+/// Maximum nesting of arrays and tuples in a type, the most `DecodeLimits::default()`
+/// decodes. Bounds the recursion of the parser, and of everything walking the types
+/// it returns.
+pub const MAX_DEPTH: usize = 32;
+
+/// Returns whether `bits` is a valid width for `Int` and `Uint`: a multiple of 8 from 8 to 256.
+pub fn is_valid_int_width(bits: usize) -> bool {
+	bits != 0 && bits <= 256 && bits % 8 == 0
+}
+
+/// Shape of a type, all that is needed to render, encode or decode it. `C` is
+/// how the type holds the components of a tuple.
+pub enum Shape<'a, K, C> {
+	/// `address`.
+	Address,
+	/// `bool`.
+	Bool,
+	/// `string`.
+	String,
+	/// `bytes`.
+	Bytes,
+	/// `uintN`, with its width in bits.
+	Uint(usize),
+	/// `intN`, with its width in bits.
+	Int(usize),
+	/// `bytesN`, with its length.
+	FixedBytes(usize),
+	/// `T[]`, with its element type.
+	Array(&'a K),
+	/// `T[N]`, with its element type and length.
+	FixedArray(&'a K, usize),
+	/// Tuple, with its components.
+	Tuple(&'a [C]),
+}
+
+/// Solidity type seen through its `Shape`, like `ParamKind`, `StaticParamKind` or `Kind`.
+pub trait TypeShape: Sized {
+	/// Tuple component, like `Box<Self>`.
+	type Component: AsRef<Self>;
+
+	/// Returns the shape of the type.
+	fn shape(&self) -> Shape<'_, Self, Self::Component>;
+}
+
+/// Writes the canonical type, as used in signatures. Like `uint256` or `(address,bytes32)[3]`.
+pub fn write_type<K: TypeShape>(out: &mut impl fmt::Write, kind: &K) -> fmt::Result {
+	match kind.shape() {
+		Shape::Address => out.write_str("address"),
+		Shape::Bool => out.write_str("bool"),
+		Shape::String => out.write_str("string"),
+		Shape::Bytes => out.write_str("bytes"),
+		Shape::Uint(bits) => write!(out, "uint{}", bits),
+		Shape::Int(bits) => write!(out, "int{}", bits),
+		Shape::FixedBytes(len) => write!(out, "bytes{}", len),
+		Shape::Array(element) => {
+			write_type(out, element)?;
+			out.write_str("[]")
+		}
+		Shape::FixedArray(element, len) => {
+			write_type(out, element)?;
+			write!(out, "[{}]", len)
+		}
+		Shape::Tuple(components) => {
+			out.write_char('(')?;
+			write_list(out, components.iter().map(AsRef::as_ref))?;
+			out.write_char(')')
+		}
+	}
+}
+
+/// Writes `name(type1,type2,...)`, the canonical signature of a function, event or error.
+pub fn write_signature<'k, K, I>(out: &mut impl fmt::Write, name: &str, kinds: I) -> fmt::Result
+where
+	K: TypeShape + 'k,
+	I: IntoIterator<Item = &'k K>,
+{
+	out.write_str(name)?;
+	out.write_char('(')?;
+	write_list(out, kinds)?;
+	out.write_char(')')
+}
+
+/// Writes comma separated types.
+fn write_list<'k, K, I>(out: &mut impl fmt::Write, kinds: I) -> fmt::Result
+where
+	K: TypeShape + 'k,
+	I: IntoIterator<Item = &'k K>,
+{
+	for (i, kind) in kinds.into_iter().enumerate() {
+		if i > 0 {
+			out.write_char(',')?;
+		}
+		write_type(out, kind)?;
+	}
+	Ok(())
+}
+
+impl<'s> TypeShape for Kind<'s> {
+	type Component = Param<'s>;
+
+	fn shape(&self) -> Shape<'_, Self, Param<'s>> {
+		match self {
+			Kind::Address => Shape::Address,
+			Kind::Bool => Shape::Bool,
+			Kind::String => Shape::String,
+			Kind::Bytes => Shape::Bytes,
+			Kind::Uint(bits) => Shape::Uint(*bits),
+			Kind::Int(bits) => Shape::Int(*bits),
+			Kind::FixedBytes(len) => Shape::FixedBytes(*len),
+			Kind::Array(element) => Shape::Array(element),
+			Kind::FixedArray(element, len) => Shape::FixedArray(element, *len),
+			Kind::Tuple(components) => Shape::Tuple(components),
+		}
+	}
+}
+
+/// Displays the canonical type, see `write_type`.
+impl fmt::Display for Kind<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write_type(f, self)
+	}
+}
+
+struct Parser<'s> {
+	input: &'s str,
+	pos: usize,
+	/// Arrays and tuples the type being parsed is nested in.
+	depth: usize,
+}
+
+impl<'s> Parser<'s> {
+	fn new(input: &'s str) -> Self {
+		Parser { input, pos: 0, depth: 0 }
+	}
+
+	fn rest(&self) -> &'s str {
+		&self.input[self.pos..]
+	}
+
+	fn skip_whitespace(&mut self) {
+		let rest = self.rest();
+		self.pos += rest.len() - rest.trim_start().len();
+	}
+
+	fn peek(&mut self) -> Option<char> {
+		self.skip_whitespace();
+		self.rest().chars().next()
+	}
+
+	fn eat(&mut self, c: char) -> bool {
+		if self.peek() == Some(c) {
+			self.pos += c.len_utf8();
+			true
+		} else {
+			false
+		}
+	}
+
+	fn expect(&mut self, c: char) -> Result<()> {
+		if self.eat(c) {
+			Ok(())
+		} else {
+			Err(SyntaxError::InvalidSignature(self.pos))
+		}
+	}
+
+	fn expect_end(&mut self) -> Result<()> {
+		match self.peek() {
+			None => Ok(()),
+			Some(_) => Err(SyntaxError::InvalidSignature(self.pos)),
+		}
+	}
+
+	/// Reads an identifier or keyword, returning `None` if there is none at the current position.
+	fn ident(&mut self) -> Option<&'s str> {
+		self.skip_whitespace();
+		let rest = self.rest();
+		let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$')).unwrap_or(rest.len());
+		if len == 0 || rest.starts_with(|c: char| c.is_ascii_digit()) {
+			return None;
+		}
+		self.pos += len;
+		Some(&rest[..len])
+	}
+
+	/// Reads the next identifier only if it is `keyword`.
+	fn keyword(&mut self, keyword: &str) -> bool {
+		let start = self.pos;
+		match self.ident() {
+			Some(ident) if ident == keyword => true,
+			_ => {
+				self.pos = start;
+				false
+			}
+		}
+	}
+
+	/// Reads the length of a fixed array, rejecting zero and leading zeros.
+	fn length(&mut self) -> Option<usize> {
+		self.skip_whitespace();
+		let rest = self.rest();
+		let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+		if rest.starts_with('0') {
+			return None;
+		}
+		let length = rest[..len].parse().ok()?;
+		self.pos += len;
+		Some(length)
+	}
+
+	/// Enters an array or tuple starting at `pos`, failing if that nests deeper than `MAX_DEPTH`.
+	fn nest(&mut self, pos: usize) -> Result<()> {
+		if self.depth >= MAX_DEPTH {
+			return Err(SyntaxError::LimitExceeded(pos));
+		}
+		self.depth += 1;
+		Ok(())
+	}
+
+	fn kind(&mut self) -> Result<Kind<'s>> {
+		self.skip_whitespace();
+		let start = self.pos;
+		let depth = self.depth;
+		let mut kind = if self.peek() == Some('(') {
+			self.tuple()?
+		} else {
+			let ident = self.ident().ok_or(SyntaxError::InvalidType(start))?;
+			if ident == "tuple" && self.peek() == Some('(') {
+				self.tuple()?
+			} else {
+				elementary(ident).ok_or(SyntaxError::InvalidType(start))?
+			}
+		};
+
+		while self.eat('[') {
+			self.nest(self.pos - 1)?;
+			kind = if self.eat(']') {
+				Kind::Array(Box::new(kind))
+			} else {
+				let size = self.length().ok_or(SyntaxError::InvalidType(self.pos))?;
+				self.expect(']')?;
+				Kind::FixedArray(Box::new(kind), size)
+			};
+		}
+
+		self.depth = depth;
+		Ok(kind)
+	}
+
+	/// Parses `(type [name], ...)`, with at least one component.
+	fn tuple(&mut self) -> Result<Kind<'s>> {
+		self.skip_whitespace();
+		let start = self.pos;
+		self.nest(start)?;
+		let components = self.list(|parser| {
+			let kind = parser.kind()?;
+			let name = parser.ident();
+			Ok(Param { kind, indexed: false, name })
+		})?;
+		if components.is_empty() {
+			return Err(SyntaxError::InvalidType(start));
+		}
+		self.depth -= 1;
+		Ok(Kind::Tuple(components))
+	}
+
+	/// Parses `type [indexed] [location] [name]`, the data location is ignored.
+	fn param(&mut self, allow_indexed: bool) -> Result<Param<'s>> {
+		let kind = self.kind()?;
+		let indexed = allow_indexed && self.keyword("indexed");
+		let _ = self.keyword("memory") || self.keyword("calldata") || self.keyword("storage");
+		let name = self.ident();
+		Ok(Param { kind, indexed, name })
+	}
+
+	/// Parses a parenthesised, comma separated list.
+	fn list<T, F>(&mut self, mut item: F) -> Result<Vec<T>>
+	where
+		F: FnMut(&mut Self) -> Result<T>,
+	{
+		self.expect('(')?;
+		let mut items = Vec::new();
+		if self.eat(')') {
+			return Ok(items);
+		}
+		loop {
+			items.push(item(self)?);
+			if self.eat(')') {
+				return Ok(items);
+			}
+			self.expect(',')?;
+		}
+	}
+
+	/// Reads the name of an event or function, skipping the optional leading
+	/// `keyword`. The keyword itself is reserved, so it is never a name.
+	fn name(&mut self, keyword: &str) -> Result<&'s str> {
+		self.keyword(keyword);
+		self.skip_whitespace();
+		let start = self.pos;
+		match self.ident() {
+			Some(ident) if ident != keyword => Ok(ident),
+			_ => Err(SyntaxError::InvalidSignature(start)),
+		}
+	}
+}
+
+/// Maps an elementary type name to its kind, `None` if it is not a valid type.
+fn elementary(ident: &str) -> Option<Kind<'static>> {
+	/// Parses the size suffix of `uintN`, `intN` and `bytesN`, rejecting leading zeros.
+	fn size(suffix: &str) -> Option<usize> {
+		if suffix.starts_with('0') {
+			return None;
+		}
+		suffix.parse().ok()
+	}
+
+	let kind = match ident {
+		"address" => Kind::Address,
+		"bool" => Kind::Bool,
+		"string" => Kind::String,
+		"bytes" => Kind::Bytes,
+		"uint" => Kind::Uint(256),
+		"int" => Kind::Int(256),
+		_ => {
+			if let Some(bits) = ident.strip_prefix("uint").and_then(size) {
+				Kind::Uint(bits)
+			} else if let Some(bits) = ident.strip_prefix("int").and_then(size) {
+				Kind::Int(bits)
+			} else if let Some(len) = ident.strip_prefix("bytes").and_then(size) {
+				Kind::FixedBytes(len)
+			} else {
+				return None;
+			}
+		}
+	};
+
+	match kind {
+		Kind::Uint(bits) | Kind::Int(bits) if !is_valid_int_width(bits) => None,
+		Kind::FixedBytes(len) if len == 0 || len > 32 => None,
+		kind => Some(kind),
+	}
+}
+
+/// Parses a type like `uint256`, `bytes32[]` or `tuple(uint8 id, bytes32[] proof)[]`.
+pub fn parse_kind(input: &str) -> Result<Kind<'_>> {
+	let mut parser = Parser::new(input);
+	let kind = parser.kind()?;
+	parser.expect_end()?;
+	Ok(kind)
+}
+
+/// Parses an event param like `address indexed from`.
+pub fn parse_param(input: &str) -> Result<Param<'_>> {
+	let mut parser = Parser::new(input);
+	let param = parser.param(true)?;
+	parser.expect_end()?;
+	Ok(param)
+}
+
+/// Parses an event like `Transfer(address indexed,address indexed,uint256)` or
+/// `event Transfer(address indexed from, address indexed to, uint256 value) anonymous`.
+pub fn parse_event(input: &str) -> Result<Event<'_>> {
+	let mut parser = Parser::new(input);
+	let name = parser.name("event")?;
+	let inputs = parser.list(|parser| parser.param(true))?;
+	let anonymous = parser.keyword("anonymous");
+	parser.eat(';');
+	parser.expect_end()?;
+	Ok(Event { name, inputs, anonymous })
+}
+
+/// Parses a function like `transfer(address,uint256)` or
+/// `function balanceOf(address owner) external view returns (uint256)`.
+pub fn parse_function(input: &str) -> Result<Function<'_>> {
+	let mut parser = Parser::new(input);
+	let name = parser.name("function")?;
+	let inputs = parser.list(|parser| parser.param(false))?;
+
+	let mut state_mutability = Mutability::NonPayable;
+	let mut outputs = Vec::new();
+	loop {
+		parser.skip_whitespace();
+		let start = parser.pos;
+		match parser.ident() {
+			None => break,
+			Some("external") | Some("public") | Some("internal") | Some("private") => {}
+			Some("pure") => state_mutability = Mutability::Pure,
+			Some("view") => state_mutability = Mutability::View,
+			Some("payable") => state_mutability = Mutability::Payable,
+			Some("nonpayable") => state_mutability = Mutability::NonPayable,
+			Some("returns") => outputs = parser.list(|parser| parser.param(false))?,
+			Some(_) => return Err(SyntaxError::InvalidSignature(start)),
+		}
+	}
+	parser.eat(';');
+	parser.expect_end()?;
+
+	Ok(Function { name, inputs, outputs, state_mutability })
+}
+
+/// Parses a custom error like `InsufficientBalance(uint256,uint256)` or
+/// `error InsufficientBalance(uint256 available, uint256 required)`.
+pub fn parse_error(input: &str) -> Result<AbiError<'_>> {
+	let mut parser = Parser::new(input);
+	let name = parser.name("error")?;
+	let inputs = parser.list(|parser| parser.param(false))?;
+	parser.eat(';');
+	parser.expect_end()?;
+	Ok(AbiError { name, inputs })
+}
@@ -6,12 +6,29 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use ethabi_decode::{decode, ParamKind, Token};
+use ethabi_decode::{decode, decode_strict, ParamKind, Token};
 use hex_literal::hex;
 use paste;
 
 macro_rules! test_encode_decode {
 	(name: $name:tt, types: $types:expr, tokens: $tokens:expr, data: $data:tt) => {
+		test_encode_decode! { name: $name, types: $types, tokens: $tokens, data: $data, strict: true }
+	};
+	(name: $name:tt, types: $types:expr, tokens: $tokens:expr, data: $data:tt, strict: true) => {
+		test_encode_decode! { name: $name, types: $types, tokens: $tokens, data: $data, strict: false }
+
+		paste::item! {
+			#[test]
+			fn [<decode_strict_ $name>]() {
+				let encoded = hex!($data);
+				let expected = $tokens;
+				let decoded = decode_strict(&$types, &encoded).unwrap();
+				assert_eq!(decoded, expected);
+			}
+		}
+	};
+	// Data that is valid but not canonical for the given types, only the lenient decoder accepts it.
+	(name: $name:tt, types: $types:expr, tokens: $tokens:expr, data: $data:tt, strict: false) => {
 		paste::item! {
 			#[test]
 			fn [<decode_ $name>]() {
@@ -101,7 +118,8 @@ test_encode_decode! {
 	name: int,
 	types: [ParamKind::Int(32)],
 	tokens: [Token::Int([0x11u8; 32].into())],
-	data: "1111111111111111111111111111111111111111111111111111111111111111"
+	data: "1111111111111111111111111111111111111111111111111111111111111111",
+	strict: false
 }
 test_encode_decode! {
 	name: int2,
@@ -119,7 +137,8 @@ test_encode_decode! {
 	name: uint,
 	types: [ParamKind::Uint(32)],
 	tokens: [Token::Uint([0x11u8; 32].into())],
-	data: "1111111111111111111111111111111111111111111111111111111111111111"
+	data: "1111111111111111111111111111111111111111111111111111111111111111",
+	strict: false
 }
 test_encode_decode! {
 	name: uint2,
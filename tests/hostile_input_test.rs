@@ -0,0 +1,230 @@
+// Copyright 2020 Snowfork
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE or
+// http://www.apache.org/licenses/LICENSE-2.0>. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Hostile input must surface as an `Err` (or a well-formed `Ok`), never as a panic.
+//!
+//! The `never_panics` tests are randomized property tests: they run inputs
+//! drawn from a fixed-seed generator through every decoding entry point, not
+//! inputs found by a fuzzer. The others are hand-written cases for the offset
+//! and length layouts that used to panic.
+
+use ethabi_decode::{
+	decode, decode_borrowed, decode_revert, decode_strict, signature, Event, EventRegistry, Function, FunctionParam,
+	LazyDecoder, OwnedEvent, Param, ParamKind, PathSegment, StateMutability, ERROR_SELECTOR, H256, PANIC_SELECTOR,
+};
+use hex_literal::hex;
+
+fn schemas() -> Vec<Vec<ParamKind>> {
+	let bool_array = ParamKind::Array(Box::new(ParamKind::Bool));
+	let dynamic_tuple = ParamKind::Tuple(vec![Box::new(ParamKind::Uint(256)), Box::new(ParamKind::String)]);
+	vec![
+		vec![ParamKind::Address],
+		vec![ParamKind::Bytes],
+		vec![ParamKind::String, ParamKind::Uint(256)],
+		vec![ParamKind::FixedBytes(32), ParamKind::Bool],
+		vec![bool_array.clone()],
+		vec![ParamKind::Array(Box::new(ParamKind::Bytes))],
+		vec![ParamKind::Array(Box::new(bool_array.clone()))],
+		vec![ParamKind::FixedArray(Box::new(ParamKind::String), 2)],
+		vec![ParamKind::FixedArray(Box::new(bool_array), 3)],
+		vec![dynamic_tuple.clone()],
+		vec![ParamKind::Array(Box::new(dynamic_tuple.clone()))],
+		vec![ParamKind::Tuple(vec![Box::new(dynamic_tuple), Box::new(ParamKind::Bytes)])],
+	]
+}
+
+/// Registry holding a `Hostile` event and an anonymous event with the params of
+/// `schema`, along with the topic of the first.
+fn registry(schema: &[ParamKind]) -> (EventRegistry, H256) {
+	let inputs = schema.iter().map(|kind| Param::new(kind.clone(), false)).collect::<Vec<_>>();
+	let event = OwnedEvent::new("Hostile", inputs.clone(), false);
+	let topic = event.as_event().topic();
+
+	let mut registry = EventRegistry::new();
+	registry.insert(event);
+	registry.insert(OwnedEvent::new("Anonymous", inputs, true));
+	(registry, topic)
+}
+
+/// Runs `data` through every entry point that decodes params of `schema`.
+fn decode_everywhere(schema: &[ParamKind], registry: &(EventRegistry, H256), data: &[u8]) {
+	let _ = decode(schema, data);
+	let _ = decode_strict(schema, data);
+	let _ = decode_borrowed(schema, data);
+
+	if let Ok(lazy) = LazyDecoder::new(schema, data) {
+		let nested: [&[PathSegment]; 4] = [
+			&[],
+			&[PathSegment::Element(0)],
+			&[PathSegment::Element(1), PathSegment::Component(1)],
+			&[PathSegment::Component(1), PathSegment::Element(2)],
+		];
+		for i in 0..schema.len() {
+			for rest in nested {
+				let _ = lazy.get(&[&[PathSegment::Input(i)], rest].concat());
+			}
+		}
+	}
+
+	let (registry, topic) = registry;
+	let _ = registry.decode(vec![*topic], data.to_vec());
+	let _ = registry.decode(vec![], data.to_vec());
+
+	let params = schema.iter().cloned().map(FunctionParam::new).collect::<Vec<_>>();
+	let signature = signature("hostile", schema);
+	let function = Function {
+		signature: &signature,
+		inputs: &params,
+		outputs: &params,
+		state_mutability: StateMutability::NonPayable,
+	};
+	let _ = function.decode_input(&[&function.selector()[..], data].concat());
+	let _ = function.decode_output(data);
+
+	for selector in [ERROR_SELECTOR, PANIC_SELECTOR] {
+		let _ = decode_revert(&[&selector[..], data].concat());
+	}
+}
+
+/// Small deterministic xorshift generator so failures are reproducible without extra dependencies.
+struct XorShift(u64);
+
+impl XorShift {
+	fn next(&mut self) -> u64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0
+	}
+
+	/// Produces a word biased towards values that look like offsets and lengths.
+	fn word(&mut self) -> [u8; 32] {
+		let mut word = [0u8; 32];
+		match self.next() % 4 {
+			// small offset or length
+			0 => word[31] = (self.next() % 256) as u8,
+			// large u32 offset or length
+			1 => word[28..].copy_from_slice(&(self.next() as u32).to_be_bytes()),
+			// all ones
+			2 => word = [0xff; 32],
+			// random noise
+			_ => word.iter_mut().for_each(|b| *b = self.next() as u8),
+		}
+		word
+	}
+}
+
+#[test]
+fn decode_random_words_never_panics() {
+	let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+	for schema in schemas() {
+		let registry = registry(&schema);
+		for _ in 0..2000 {
+			let len = (rng.next() % 12) as usize;
+			let data: Vec<u8> = (0..len).flat_map(|_| rng.word()).collect();
+			decode_everywhere(&schema, &registry, &data);
+		}
+	}
+}
+
+#[test]
+fn decode_unaligned_data_never_panics() {
+	let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+	for schema in schemas() {
+		let registry = registry(&schema);
+		for _ in 0..500 {
+			let len = (rng.next() % 200) as usize;
+			let data: Vec<u8> = (0..len).map(|_| rng.next() as u8).collect();
+			decode_everywhere(&schema, &registry, &data);
+		}
+	}
+}
+
+#[test]
+fn decode_array_offset_past_end() {
+	let encoded = hex!("00000000000000000000000000000000000000000000000000000000000000e0");
+	assert!(decode(&[ParamKind::Array(Box::new(ParamKind::Bool))], &encoded).is_err());
+}
+
+#[test]
+fn decode_array_length_word_is_last_word() {
+	// the length word exists but there is nothing after it
+	let encoded = hex!(
+		"
+		0000000000000000000000000000000000000000000000000000000000000020
+		0000000000000000000000000000000000000000000000000000000000000001
+	"
+	);
+	assert!(decode(&[ParamKind::Array(Box::new(ParamKind::Bool))], &encoded).is_err());
+}
+
+#[test]
+fn decode_array_huge_length() {
+	let encoded = hex!(
+		"
+		0000000000000000000000000000000000000000000000000000000000000020
+		00000000000000000000000000000000000000000000000000000000ffffffff
+		0000000000000000000000000000000000000000000000000000000000000001
+	"
+	);
+	assert!(decode(&[ParamKind::Array(Box::new(ParamKind::Bool))], &encoded).is_err());
+}
+
+#[test]
+fn decode_bytes_huge_length() {
+	let encoded = hex!(
+		"
+		0000000000000000000000000000000000000000000000000000000000000020
+		00000000000000000000000000000000000000000000000000000000ffffffff
+		1234000000000000000000000000000000000000000000000000000000000000
+	"
+	);
+	assert!(decode(&[ParamKind::Bytes], &encoded).is_err());
+	assert!(decode(&[ParamKind::String], &encoded).is_err());
+}
+
+#[test]
+fn decode_dynamic_tuple_offset_past_end() {
+	let tuple = ParamKind::Tuple(vec![Box::new(ParamKind::Bool), Box::new(ParamKind::Bytes)]);
+	let encoded = hex!("00000000000000000000000000000000000000000000000000000000ffffffe0");
+	assert!(decode(&[tuple], &encoded).is_err());
+}
+
+#[test]
+fn decode_dynamic_fixed_array_offset_past_end() {
+	let array = ParamKind::FixedArray(Box::new(ParamKind::String), 2);
+	let encoded = hex!(
+		"
+		0000000000000000000000000000000000000000000000000000000000000040
+		0000000000000000000000000000000000000000000000000000000000000000
+	"
+	);
+	assert!(decode(&[array], &encoded).is_err());
+}
+
+#[test]
+fn decode_offset_word_overflowing_u32() {
+	let encoded = hex!("0000000000000000000000000000000000000000000000000000000100000000");
+	assert!(decode(&[ParamKind::Bytes], &encoded).is_err());
+}
+
+#[test]
+fn decode_event_with_hostile_data_never_panics() {
+	let inputs =
+		[Param::new(ParamKind::Address, true), Param::new(ParamKind::Array(Box::new(ParamKind::Bytes)), false)];
+	let event = Event { signature: "Hostile(address,bytes[])", inputs: &inputs, anonymous: true };
+
+	let mut rng = XorShift(0xdead_beef_cafe_f00d);
+	for _ in 0..2000 {
+		let topics: Vec<H256> = (0..rng.next() % 3).map(|_| H256::from(rng.word())).collect();
+		let len = (rng.next() % 8) as usize;
+		let data: Vec<u8> = (0..len).flat_map(|_| rng.word()).collect();
+		let _ = event.decode(topics.clone(), data.clone());
+		let _ = event.decode_strict(topics, data);
+	}
+}